@@ -0,0 +1,267 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::keywords::{KW_DELETE, KW_IN, KW_INSTANCEOF, KW_TYPEOF, KW_VOID};
+use crate::lexer::Position;
+use crate::operators::{
+    OP_ADD, OP_AND, OP_BIT_AND, OP_BIT_NOT, OP_BIT_OR, OP_BIT_XOR, OP_DECREMENT, OP_DIV, OP_EXP,
+    OP_GT, OP_GT_EQ, OP_INCREMENT, OP_LOOSE_EQ, OP_LOOSE_NOT_EQ, OP_LT, OP_LT_EQ, OP_MOD, OP_MUL,
+    OP_NOT, OP_NULLISH, OP_OR, OP_SHL, OP_SHR, OP_STRICT_EQ, OP_STRICT_NOT_EQ, OP_SUB, OP_USHR,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub body: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeclarationKind {
+    Var,
+    Let,
+    Const,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declarator {
+    pub name: String,
+    pub init: Option<Expression>,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDeclaration {
+    /// Empty for anonymous function expressions.
+    pub name: String,
+    /// Shared with every function value created from this declaration, so creating a closure
+    /// doesn't copy its parameters or body.
+    pub params: Rc<[String]>,
+    pub body: Rc<[Statement]>,
+    /// The function's text as written, which is what converting it to a string gives.
+    pub source: Rc<str>,
+    /// Where the function's name is, or where it starts if it has none.
+    pub position: Position,
+    /// For a declaration in a block, whether sloppy mode also copies it to a `var` of the same
+    /// name in the enclosing function when the block runs, as Annex B of the spec allows.
+    pub annex_b: bool,
+}
+
+/// A run of literal text in a template literal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateElement {
+    /// The text with its escapes decoded, or `None` if a tagged template has an invalid escape.
+    pub cooked: Option<String>,
+    pub raw: String,
+}
+
+/// The name of a property in an object literal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyKey {
+    /// An identifier, string or number key, already converted to the string it names.
+    Named(String),
+    /// `[key]`, whose value is converted to a string when the object is created.
+    Computed(Expression, Position),
+}
+
+/// A property in an object literal. Shorthand properties and methods are parsed as `key: key`
+/// and `key: function () {}`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Property {
+    Init(PropertyKey, Expression),
+    /// `...value`, which copies the properties of `value`.
+    Spread(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Declaration(DeclarationKind, Vec<Declarator>),
+    Function(FunctionDeclaration),
+    Expression(Expression),
+    Block(Vec<Statement>),
+    If(Expression, Box<Statement>, Option<Box<Statement>>),
+    While(Expression, Box<Statement>),
+    DoWhile(Box<Statement>, Expression),
+    /// `for (init; test; update) body`, where `init` is a declaration or an expression.
+    For(
+        Option<Box<Statement>>,
+        Option<Expression>,
+        Option<Expression>,
+        Box<Statement>,
+    ),
+    Break(Option<String>),
+    Continue(Option<String>),
+    Labeled(String, Box<Statement>),
+    Return(Option<Expression>),
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Exp,
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+    LooseEq,
+    LooseNotEq,
+    StrictEq,
+    StrictNotEq,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    UShr,
+    In,
+    InstanceOf,
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use BinaryOperator::*;
+        let symbol = match self {
+            Add => OP_ADD,
+            Sub => OP_SUB,
+            Mul => OP_MUL,
+            Div => OP_DIV,
+            Mod => OP_MOD,
+            Exp => OP_EXP,
+            Lt => OP_LT,
+            Gt => OP_GT,
+            LtEq => OP_LT_EQ,
+            GtEq => OP_GT_EQ,
+            LooseEq => OP_LOOSE_EQ,
+            LooseNotEq => OP_LOOSE_NOT_EQ,
+            StrictEq => OP_STRICT_EQ,
+            StrictNotEq => OP_STRICT_NOT_EQ,
+            BitAnd => OP_BIT_AND,
+            BitOr => OP_BIT_OR,
+            BitXor => OP_BIT_XOR,
+            Shl => OP_SHL,
+            Shr => OP_SHR,
+            UShr => OP_USHR,
+            In => KW_IN,
+            InstanceOf => KW_INSTANCEOF,
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOperator {
+    Minus,
+    Plus,
+    Not,
+    BitNot,
+    Typeof,
+    Void,
+    Delete,
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use UnaryOperator::*;
+        let symbol = match self {
+            Minus => OP_SUB,
+            Plus => OP_ADD,
+            Not => OP_NOT,
+            BitNot => OP_BIT_NOT,
+            Typeof => KW_TYPEOF,
+            Void => KW_VOID,
+            Delete => KW_DELETE,
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdateOperator {
+    Increment,
+    Decrement,
+}
+
+impl fmt::Display for UpdateOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            UpdateOperator::Increment => OP_INCREMENT,
+            UpdateOperator::Decrement => OP_DECREMENT,
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// `=`, or the operator a compound assignment such as `+=` or `??=` applies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssignmentOperator {
+    Assign,
+    Binary(BinaryOperator),
+    Logical(LogicalOperator),
+}
+
+/// Operators that may skip evaluating their right operand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogicalOperator {
+    And,
+    Or,
+    Nullish,
+}
+
+impl fmt::Display for LogicalOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            LogicalOperator::And => OP_AND,
+            LogicalOperator::Or => OP_OR,
+            LogicalOperator::Nullish => OP_NULLISH,
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    String(String),
+    Boolean(bool),
+    Null,
+    Array(Vec<Expression>),
+    /// An object literal's properties in source order.
+    Object(Vec<Property>),
+    Identifier(String, Position),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>, Position),
+    Logical(LogicalOperator, Box<Expression>, Box<Expression>),
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+    Unary(UnaryOperator, Box<Expression>, Position),
+    /// `++` or `--`, and whether it comes before its operand.
+    Update(UpdateOperator, bool, Box<Expression>, Position),
+    /// An assignment to an identifier or member expression, positioned at the target.
+    Assign(
+        AssignmentOperator,
+        Box<Expression>,
+        Box<Expression>,
+        Position,
+    ),
+    /// Expressions separated by the comma operator.
+    Sequence(Vec<Expression>),
+    Member(Box<Expression>, String, Position),
+    /// A computed member access such as `object[key]`.
+    Index(Box<Expression>, Box<Expression>, Position),
+    Call(Box<Expression>, Vec<Expression>, Position),
+    /// A template literal's strings and the substitutions between them, so there is always one
+    /// more string than substitution.
+    Template(Vec<TemplateElement>, Vec<Expression>, Position),
+    /// A template literal called as `tag\`...\``.
+    TaggedTemplate(
+        Box<Expression>,
+        Vec<TemplateElement>,
+        Vec<Expression>,
+        Position,
+    ),
+    Function(FunctionDeclaration),
+    /// An arrow function; a concise body is parsed as a single `return` statement.
+    Arrow(FunctionDeclaration),
+    This,
+}
@@ -0,0 +1,107 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::slice;
+
+use crate::error::{ErrorKind, JsError};
+use crate::types::{Function, Num, Properties, Type, MAX_ARRAY_LENGTH};
+
+fn log(arguments: &[Type], _this: &Type) -> Result<Type, JsError> {
+    let line: Vec<String> = arguments
+        .iter()
+        .map(|argument| match argument {
+            Type::TextString(string) => string.clone(),
+            value => value.inspect(),
+        })
+        .collect();
+    // Like Node, a failed write doesn't throw, so piping into something like `head` that exits
+    // early closes the pipe without a panic.
+    let _ = writeln!(io::stdout().lock(), "{}", line.join(" "));
+    Ok(Type::Undefined)
+}
+
+/// The global `console`, whose `log` writes its arguments to stdout separated by spaces.
+fn console() -> Type {
+    let mut console = Properties::new();
+    console.insert(String::from("log"), Function::native("log", log));
+    Type::Object(Rc::new(RefCell::new(console)))
+}
+
+/// `String.raw`, which joins the raw strings of a template with the substitutions between them.
+fn raw(arguments: &[Type], _this: &Type) -> Result<Type, JsError> {
+    let raw = match arguments.first() {
+        Some(Type::Object(strings)) => strings.borrow().get("raw").cloned(),
+        _ => None,
+    };
+    let (length, string): (usize, Box<dyn Fn(usize) -> Option<Type>>) = match raw {
+        Some(Type::Array(raw)) => {
+            let length = raw.borrow().len();
+            (
+                length,
+                Box::new(move |index| raw.borrow().get(index).cloned()),
+            )
+        }
+        Some(Type::TextString(raw)) => {
+            let units: Vec<u16> = raw.encode_utf16().collect();
+            let length = units.len();
+            let string = move |index: usize| {
+                let unit = units.get(index)?;
+                Some(Type::TextString(String::from_utf16_lossy(slice::from_ref(
+                    unit,
+                ))))
+            };
+            (length, Box::new(string))
+        }
+        Some(Type::Object(raw)) => {
+            let length = match raw.borrow().get("length").map(Type::to_number) {
+                Some(Num::F64(length)) if length > MAX_ARRAY_LENGTH as f64 => {
+                    return Err(JsError::new(
+                        ErrorKind::RangeError,
+                        "Invalid array length",
+                        None,
+                    ))
+                }
+                Some(Num::F64(length)) if length > 0f64 => length as usize,
+                _ => 0,
+            };
+            let string = move |index: usize| raw.borrow().get(&index.to_string()).cloned();
+            (length, Box::new(string))
+        }
+        _ => {
+            return Err(JsError::new(
+                ErrorKind::TypeError,
+                "Cannot convert undefined or null to object",
+                None,
+            ))
+        }
+    };
+    let mut text = String::new();
+    for index in 0..length {
+        text.push_str(&string(index).unwrap_or(Type::Undefined).to_string());
+        match arguments.get(index + 1) {
+            Some(substitution) if index + 1 < length => text.push_str(&substitution.to_string()),
+            _ => (),
+        }
+    }
+    Ok(Type::TextString(text))
+}
+
+fn string() -> Type {
+    let mut string = Properties::new();
+    string.insert(String::from("raw"), Function::native("raw", raw));
+    Type::Object(Rc::new(RefCell::new(string)))
+}
+
+/// The values every new interpreter starts out with in its global scope.
+pub fn globals() -> HashMap<String, Type> {
+    let mut globals = HashMap::new();
+    globals.insert(String::from("console"), console());
+    globals.insert(String::from("String"), string());
+    globals.insert(String::from("NaN"), Type::Number(Num::NaN));
+    globals.insert(
+        String::from("Infinity"),
+        Type::Number(Num::F64(f64::INFINITY)),
+    );
+    globals
+}
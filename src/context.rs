@@ -0,0 +1,285 @@
+use crate::error::JsError;
+use crate::interpreter_2::Interpreter;
+use crate::types::Function;
+use crate::Value;
+
+/// A JavaScript global environment that scripts can be run against.
+///
+/// Globals declared by one call to `eval` are visible to the next, so a `Context` can be
+/// fed a script piece by piece.
+///
+/// Script function calls run on the Rust stack and may nest up to [`MAX_CALL_DEPTH`] deep
+/// before a `RangeError` is thrown, as may expressions and statements up to
+/// [`MAX_NESTING_DEPTH`]. Run scripts on a thread with a stack of [`STACK_SIZE`] bytes to be
+/// sure the limits are reached before the stack runs out.
+///
+/// [`MAX_CALL_DEPTH`]: crate::MAX_CALL_DEPTH
+/// [`MAX_NESTING_DEPTH`]: crate::MAX_NESTING_DEPTH
+/// [`STACK_SIZE`]: crate::STACK_SIZE
+pub struct Context {
+    interpreter: Interpreter,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+        }
+    }
+
+    /// Runs `source` and returns the value of its last statement.
+    pub fn eval(&mut self, source: &str) -> Result<Value, JsError> {
+        self.interpreter.eval(source)
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.interpreter.get_global(name)
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.interpreter.set_global(name, value);
+    }
+
+    /// Installs a Rust closure as a global function callable from scripts.
+    ///
+    /// The closure receives the call's arguments and its `this` value, which is the object
+    /// for method calls like `obj.name()` and `undefined` otherwise. An error it returns is
+    /// thrown in the script at the call site.
+    pub fn register_fn<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value], &Value) -> Result<Value, JsError> + 'static,
+    {
+        self.set_global(name, Function::native(name, function));
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod context_tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::lexer::Position;
+    use crate::types::{Num, Properties};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn globals_persist_between_evals() {
+        let mut context = Context::new();
+        context.eval("var a = 10;").unwrap();
+        match context.eval("a * 2").unwrap() {
+            Value::Number(Num::F64(value)) => assert_eq!(value, 20f64),
+            actual => panic!("Expected 20 found {:?}", actual),
+        }
+    }
+
+    #[test]
+    fn later_evals_cannot_redeclare_lexical_globals() {
+        let mut context = Context::new();
+        context.eval("const c = 1; let l = 2; var v = 3;").unwrap();
+        for (source, name) in [
+            ("const c = 2", "c"),
+            ("var c = 3", "c"),
+            ("var c", "c"),
+            ("function c() {}", "c"),
+            ("let l = 4", "l"),
+            ("var v; let v = 5", "v"),
+        ] {
+            let error = context.eval(source).unwrap_err();
+            assert_eq!(error.kind, ErrorKind::SyntaxError, "{}", source);
+            assert_eq!(
+                error.message,
+                format!("Identifier '{}' has already been declared", name),
+                "{}",
+                source
+            );
+        }
+        assert_eq!(context.eval("c + l").unwrap().to_string(), "3");
+        context.eval("var v = 6; { let c = 7 }").unwrap();
+        assert_eq!(context.eval("v").unwrap().to_string(), "6");
+        let error = context.eval("\nfunction l() {}").unwrap_err();
+        assert_eq!(
+            error.position,
+            Some(Position {
+                line: 2,
+                column: 10
+            })
+        );
+        // A block function only hoists to the global scope where no `let` holds its name.
+        context.eval("{ function l() {} }").unwrap();
+        assert_eq!(context.eval("l").unwrap().to_string(), "2");
+    }
+
+    #[test]
+    fn runaway_recursion_throws_a_range_error() {
+        let thread = thread::Builder::new().stack_size(crate::STACK_SIZE);
+        let results = thread
+            .spawn(|| {
+                let mut context = Context::new();
+                let error = context
+                    .eval("function f() { return f() }\nf()")
+                    .unwrap_err();
+                let deepest = context
+                    .eval("function g(n) { return n ? g(n - 1) + 1 : 0 }\ng(999)")
+                    .map(|value| value.to_string());
+                (error, deepest)
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        let (error, deepest) = results;
+        assert_eq!(error.kind, ErrorKind::RangeError);
+        assert_eq!(error.message, "Maximum call stack size exceeded");
+        assert_eq!(error.position.map(|p| (p.line, p.column)), Some((1, 24)));
+        assert_eq!(deepest, Ok(String::from("999")));
+    }
+
+    #[test]
+    fn deep_nesting_throws_instead_of_overflowing() {
+        let nested = |open: &str, inner: &str, close: &str| {
+            let depth = 200_000;
+            format!("{}{}{}", open.repeat(depth), inner, close.repeat(depth))
+        };
+        let sources = [
+            nested("(", "1", ")"),
+            nested("[", "", "]"),
+            nested("{", "", "}"),
+            nested("- ", "1", ""),
+            nested("if (1) ", ";", ""),
+            nested("function f() {", "", "}"),
+            vec!["1"; 200_000].join(" + "),
+            nested("", "f", "()"),
+            // Nested calls count towards the limit along with the expressions in each.
+            format!(
+                "function f(n) {{ return n ? {}f(n - 1){} : 0 }} f(999)",
+                "-(".repeat(100),
+                ")".repeat(100)
+            ),
+            nested("`${", "1", "}`"),
+        ];
+        let thread = thread::Builder::new().stack_size(crate::STACK_SIZE);
+        let results = thread
+            .spawn(move || {
+                let mut context = Context::new();
+                let errors: Vec<JsError> = sources
+                    .iter()
+                    .map(|source| context.eval(source).unwrap_err())
+                    .collect();
+                let shallow = context
+                    .eval(&format!("{}1{}", "(".repeat(1000), ")".repeat(1000)))
+                    .map(|value| value.to_string());
+                (errors, shallow)
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        let (errors, shallow) = results;
+        for error in &errors[..errors.len() - 1] {
+            assert_eq!(error.kind, ErrorKind::RangeError, "{}", error);
+            assert_eq!(error.message, "Maximum call stack size exceeded");
+        }
+        let templates = &errors[errors.len() - 1];
+        assert_eq!(templates.kind, ErrorKind::SyntaxError);
+        assert_eq!(templates.message, "Template literals are nested too deeply");
+        assert_eq!(shallow, Ok(String::from("1")));
+    }
+
+    #[test]
+    fn set_and_get_global() {
+        let mut context = Context::new();
+        context.set_global("name", Value::TextString(String::from("jake")));
+        context.eval("var greeting = 'hi ' + name;").unwrap();
+        match context.get_global("greeting") {
+            Some(Value::TextString(greeting)) => assert_eq!(greeting, "hi jake"),
+            actual => panic!("Expected string found {:?}", actual),
+        }
+        assert!(context.get_global("missing").is_none());
+    }
+
+    #[test]
+    fn errors_are_returned() {
+        let mut context = Context::new();
+        let error = context.eval("missing;").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ReferenceError);
+    }
+
+    #[test]
+    fn register_fn_receives_arguments() {
+        let mut context = Context::new();
+        context.register_fn("double", |args, _this| match args.first() {
+            Some(Value::Number(Num::F64(value))) => Ok(Value::Number(Num::F64(value * 2f64))),
+            _ => Ok(Value::Number(Num::NaN)),
+        });
+        match context.eval("double(21) + double()").unwrap() {
+            Value::Number(Num::NaN) => (),
+            actual => panic!("Expected NaN found {:?}", actual),
+        }
+        match context.eval("double(21)").unwrap() {
+            Value::Number(Num::F64(value)) => assert_eq!(value, 42f64),
+            actual => panic!("Expected 42 found {:?}", actual),
+        }
+    }
+
+    #[test]
+    fn register_fn_captures_host_state() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut context = Context::new();
+        let sink = Rc::clone(&log);
+        context.register_fn("record", move |args, _this| {
+            sink.borrow_mut()
+                .extend(args.iter().map(|arg| arg.to_string()));
+            Ok(Value::Undefined)
+        });
+        context.eval("record('a', 1); record(null);").unwrap();
+        assert_eq!(*log.borrow(), vec!["a", "1", "null"]);
+    }
+
+    #[test]
+    fn register_fn_errors_are_thrown_at_call_site() {
+        let mut context = Context::new();
+        context.register_fn("config", |_args, _this| {
+            Err(JsError::new(ErrorKind::RangeError, "no such key", None))
+        });
+        let error = context.eval("var a = 1;\nconfig('x');").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::RangeError);
+        assert_eq!(error.message, "no such key");
+        assert_eq!(error.position.map(|p| (p.line, p.column)), Some((2, 7)));
+    }
+
+    #[test]
+    fn functions_expose_only_their_name() {
+        let mut context = Context::new();
+        match context
+            .eval("function add(a, b) { return a + b }\nadd")
+            .unwrap()
+        {
+            Value::Function(function) => assert_eq!(function.name(), "add"),
+            actual => panic!("Expected function found {:?}", actual),
+        }
+    }
+
+    #[test]
+    fn method_calls_receive_this() {
+        let mut service = Properties::new();
+        service.insert(String::from("name"), Value::TextString(String::from("db")));
+        service.insert(
+            String::from("describe"),
+            Function::native("describe", |_args, this| match this {
+                Value::Object(object) => Ok(object.borrow()["name"].clone()),
+                _ => Ok(Value::Undefined),
+            }),
+        );
+        let mut context = Context::new();
+        context.set_global("service", Value::Object(Rc::new(RefCell::new(service))));
+        match context.eval("service.describe()").unwrap() {
+            Value::TextString(name) => assert_eq!(name, "db"),
+            actual => panic!("Expected string found {:?}", actual),
+        }
+    }
+}
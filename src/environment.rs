@@ -0,0 +1,312 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::types::Type;
+
+#[derive(Clone)]
+struct Binding {
+    /// `None` until a `let` or `const` declaration runs; reading it before then is an error.
+    value: Option<Type>,
+    mutable: bool,
+    /// Declared by `let` or `const`, so nothing else may be declared with its name in the
+    /// same scope.
+    lexical: bool,
+}
+
+/// Why looking up or assigning to a name failed.
+#[derive(Debug, PartialEq)]
+pub enum BindingError {
+    NotDefined,
+    /// The name is a `let` or `const` that is still in its temporal dead zone.
+    Uninitialized,
+    /// The name is a `const`.
+    Constant,
+    /// The name is already a `let` or `const` in the scope it was being declared in.
+    Redeclared,
+}
+
+struct Scope {
+    bindings: HashMap<String, Binding>,
+    parent: Option<Environment>,
+    /// Whether `var` declarations made inside this scope belong to it. True for function
+    /// bodies and the global scope, false for blocks.
+    function: bool,
+}
+
+/// A shared handle to one scope in a chain of scopes ending at the global scope.
+#[derive(Clone)]
+pub struct Environment(Rc<RefCell<Scope>>);
+
+impl Environment {
+    fn new(parent: Option<Environment>, function: bool) -> Self {
+        Environment(Rc::new(RefCell::new(Scope {
+            bindings: HashMap::new(),
+            parent,
+            function,
+        })))
+    }
+
+    pub fn global(bindings: HashMap<String, Type>) -> Self {
+        let global = Self::new(None, true);
+        for (name, value) in bindings {
+            global.define(&name, value);
+        }
+        global
+    }
+
+    /// Creates the scope for a function body, enclosed by `self`.
+    pub fn function(&self) -> Self {
+        Self::new(Some(self.clone()), true)
+    }
+
+    /// Creates the scope for a block, enclosed by `self`.
+    pub fn block(&self) -> Self {
+        Self::new(Some(self.clone()), false)
+    }
+
+    /// Creates a sibling of this scope holding copies of its bindings, so each iteration of
+    /// a `for (let …)` loop gets its own variables.
+    pub fn copy(&self) -> Self {
+        let inner = self.0.borrow();
+        Environment(Rc::new(RefCell::new(Scope {
+            bindings: inner.bindings.clone(),
+            parent: inner.parent.clone(),
+            function: inner.function,
+        })))
+    }
+
+    /// The nearest enclosing scope that `var` declarations are hoisted to.
+    pub fn function_scope(&self) -> Self {
+        let mut scope = self.clone();
+        loop {
+            let parent = {
+                let inner = scope.0.borrow();
+                if inner.function {
+                    return scope.clone();
+                }
+                inner.parent.clone()
+            };
+            match parent {
+                Some(parent) => scope = parent,
+                None => return scope,
+            }
+        }
+    }
+
+    pub fn has_own(&self, name: &str) -> bool {
+        self.0.borrow().bindings.contains_key(name)
+    }
+
+    /// Creates or overwrites a mutable binding in this scope, shadowing any outer binding.
+    /// Used for bindings the interpreter makes itself, such as parameters and `this`.
+    pub fn define(&self, name: &str, value: Type) {
+        self.0.borrow_mut().bindings.insert(
+            String::from(name),
+            Binding {
+                value: Some(value),
+                mutable: true,
+                lexical: false,
+            },
+        );
+    }
+
+    /// Declares a `var` or function in this scope. It may replace an earlier `var`, but not a
+    /// `let` or `const`, which a previous script run in the same context could have declared.
+    pub fn declare(&self, name: &str, value: Type) -> Result<(), BindingError> {
+        self.insert(name, Some(value), true, false)
+    }
+
+    /// Declares a hoisted `var` as `undefined`, leaving it as it was if it's already declared.
+    pub fn declare_hoisted(&self, name: &str) -> Result<(), BindingError> {
+        if self.has_own(name) {
+            return match self.0.borrow().bindings[name].lexical {
+                true => Err(BindingError::Redeclared),
+                false => Ok(()),
+            };
+        }
+        self.declare(name, Type::Undefined)
+    }
+
+    /// Creates a `let` or `const` binding that can't be used until it is initialized.
+    pub fn declare_uninitialized(&self, name: &str, mutable: bool) -> Result<(), BindingError> {
+        self.insert(name, None, mutable, true)
+    }
+
+    /// Gives a binding created by `declare_uninitialized` its first value.
+    pub fn initialize(&self, name: &str, value: Type) {
+        if let Some(binding) = self.0.borrow_mut().bindings.get_mut(name) {
+            binding.value = Some(value);
+        }
+    }
+
+    fn insert(
+        &self,
+        name: &str,
+        value: Option<Type>,
+        mutable: bool,
+        lexical: bool,
+    ) -> Result<(), BindingError> {
+        let mut inner = self.0.borrow_mut();
+        if inner
+            .bindings
+            .get(name)
+            .is_some_and(|binding| binding.lexical)
+        {
+            return Err(BindingError::Redeclared);
+        }
+        let binding = Binding {
+            value,
+            mutable,
+            lexical,
+        };
+        inner.bindings.insert(String::from(name), binding);
+        Ok(())
+    }
+
+    /// Finds the scope that `name` resolves to, walking outward from this one.
+    fn resolve(&self, name: &str) -> Option<Self> {
+        let mut scope = self.clone();
+        loop {
+            let parent = {
+                let inner = scope.0.borrow();
+                if inner.bindings.contains_key(name) {
+                    return Some(scope.clone());
+                }
+                inner.parent.clone()
+            };
+            scope = parent?;
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Type, BindingError> {
+        let scope = self.resolve(name).ok_or(BindingError::NotDefined)?;
+        let inner = scope.0.borrow();
+        inner.bindings[name]
+            .value
+            .clone()
+            .ok_or(BindingError::Uninitialized)
+    }
+
+    /// Updates the nearest binding of `name`.
+    pub fn assign(&self, name: &str, value: Type) -> Result<(), BindingError> {
+        let scope = self.resolve(name).ok_or(BindingError::NotDefined)?;
+        let mut inner = scope.0.borrow_mut();
+        let binding = inner.bindings.get_mut(name).unwrap();
+        match binding {
+            Binding { value: None, .. } => Err(BindingError::Uninitialized),
+            Binding { mutable: false, .. } => Err(BindingError::Constant),
+            binding => {
+                binding.value = Some(value);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod environment_tests {
+    use super::*;
+    use crate::types::Num;
+
+    fn number(value: f64) -> Type {
+        Type::Number(Num::F64(value))
+    }
+
+    fn get_number(environment: &Environment, name: &str) -> Option<f64> {
+        match environment.get(name) {
+            Ok(Type::Number(Num::F64(value))) => Some(value),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn lookup_walks_outward() {
+        let global = Environment::global(HashMap::new());
+        global.define("a", number(1f64));
+        let block = global.function().block();
+        assert_eq!(get_number(&block, "a"), Some(1f64));
+        assert_eq!(get_number(&block, "b"), None);
+    }
+
+    #[test]
+    fn inner_declarations_shadow_outer() {
+        let global = Environment::global(HashMap::new());
+        global.define("a", number(1f64));
+        let block = global.block();
+        block.define("a", number(2f64));
+        assert_eq!(get_number(&block, "a"), Some(2f64));
+        assert_eq!(get_number(&global, "a"), Some(1f64));
+    }
+
+    #[test]
+    fn assign_updates_nearest_binding() {
+        let global = Environment::global(HashMap::new());
+        global.define("a", number(1f64));
+        let block = global.block();
+        assert_eq!(block.assign("a", number(3f64)), Ok(()));
+        assert_eq!(
+            block.assign("b", number(3f64)),
+            Err(BindingError::NotDefined)
+        );
+        assert_eq!(get_number(&global, "a"), Some(3f64));
+    }
+
+    #[test]
+    fn function_scope_skips_blocks() {
+        let global = Environment::global(HashMap::new());
+        let function = global.function();
+        function
+            .block()
+            .block()
+            .function_scope()
+            .define("a", number(1f64));
+        assert!(function.has_own("a"));
+        assert!(!global.has_own("a"));
+    }
+
+    #[test]
+    fn uninitialized_bindings() {
+        let global = Environment::global(HashMap::new());
+        global.declare_uninitialized("a", true).unwrap();
+        assert_eq!(global.get("a").unwrap_err(), BindingError::Uninitialized);
+        assert_eq!(
+            global.assign("a", number(1f64)),
+            Err(BindingError::Uninitialized)
+        );
+        global.initialize("a", number(2f64));
+        assert_eq!(get_number(&global, "a"), Some(2f64));
+    }
+
+    #[test]
+    fn constants_reject_assignment() {
+        let global = Environment::global(HashMap::new());
+        global.declare_uninitialized("a", false).unwrap();
+        global.initialize("a", number(1f64));
+        assert_eq!(
+            global.assign("a", number(2f64)),
+            Err(BindingError::Constant)
+        );
+        assert_eq!(get_number(&global, "a"), Some(1f64));
+    }
+
+    #[test]
+    fn lexical_bindings_reject_redeclaration() {
+        let global = Environment::global(HashMap::new());
+        global.declare("a", number(1f64)).unwrap();
+        assert_eq!(global.declare_hoisted("a"), Ok(()));
+        assert_eq!(get_number(&global, "a"), Some(1f64));
+        assert_eq!(global.declare("a", number(2f64)), Ok(()));
+        global.declare_uninitialized("b", false).unwrap();
+        assert_eq!(
+            global.declare_uninitialized("b", true),
+            Err(BindingError::Redeclared)
+        );
+        assert_eq!(
+            global.declare("b", number(3f64)),
+            Err(BindingError::Redeclared)
+        );
+        assert_eq!(global.declare_hoisted("b"), Err(BindingError::Redeclared));
+        assert_eq!(global.block().declare_uninitialized("b", true), Ok(()));
+    }
+}
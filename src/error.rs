@@ -0,0 +1,122 @@
+use std::fmt;
+
+use crate::lexer::Position;
+
+/// The JavaScript error constructor each kind of failure corresponds to.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    SyntaxError,
+    ReferenceError,
+    TypeError,
+    RangeError,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ErrorKind::*;
+        match self {
+            SyntaxError => write!(f, "SyntaxError"),
+            ReferenceError => write!(f, "ReferenceError"),
+            TypeError => write!(f, "TypeError"),
+            RangeError => write!(f, "RangeError"),
+        }
+    }
+}
+
+/// An error raised while lexing, parsing or running a script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Where in the source the error was raised, if it is known.
+    pub position: Option<Position>,
+}
+
+impl JsError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>, position: Option<Position>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            position,
+        }
+    }
+
+    pub fn syntax(message: impl Into<String>, position: Position) -> Self {
+        Self::new(ErrorKind::SyntaxError, message, Some(position))
+    }
+
+    pub fn reference(message: impl Into<String>, position: Position) -> Self {
+        Self::new(ErrorKind::ReferenceError, message, Some(position))
+    }
+
+    pub fn type_error(message: impl Into<String>, position: Position) -> Self {
+        Self::new(ErrorKind::TypeError, message, Some(position))
+    }
+
+    /// Renders the lines of `source` around the error with a caret under its column.
+    pub fn code_frame(&self, source: &str) -> String {
+        let position = match self.position {
+            Some(position) => position,
+            None => return String::new(),
+        };
+        let lines: Vec<&str> = source.lines().collect();
+        let first = position.line.saturating_sub(2);
+        let last = usize::min(position.line + 1, lines.len());
+        let width = last.to_string().len();
+        let mut frame = String::new();
+        for (index, line) in lines.iter().enumerate().take(last).skip(first) {
+            let number = index + 1;
+            let marker = if number == position.line { '>' } else { ' ' };
+            frame.push_str(&format!(
+                "{} {:>width$} | {}\n",
+                marker,
+                number,
+                line,
+                width = width
+            ));
+            if number == position.line {
+                let padding: String = line
+                    .chars()
+                    .take(position.column - 1)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                frame.push_str(&format!("  {:>width$} | {}^\n", "", padding, width = width));
+            }
+        }
+        frame
+    }
+}
+
+impl fmt::Display for JsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)?;
+        if let Some(position) = self.position {
+            write!(f, " ({}:{})", position.line, position.column)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for JsError {}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn display_with_position() {
+        let error = JsError::reference("a is not defined", Position { line: 2, column: 9 });
+        assert_eq!(error.to_string(), "ReferenceError: a is not defined (2:9)");
+    }
+
+    #[test]
+    fn code_frame_marks_column() {
+        let source = "var a = 1;\nvar b = c;\nvar d = 2;\nvar e = 3;";
+        let error = JsError::reference("c is not defined", Position { line: 2, column: 9 });
+        assert_eq!(
+            error.code_frame(source),
+            "  1 | var a = 1;\n> 2 | var b = c;\n    |         ^\n  3 | var d = 2;\n"
+        );
+    }
+}
@@ -1,300 +1,1267 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::mem;
+use std::ops::ControlFlow;
+use std::rc::Rc;
+use std::slice;
 
-use crate::keywords::{KW_CONST, KW_FUNCTION, KW_LET, KW_VAR};
-use crate::operators::{OP_ADD, OP_DIV, OP_EQ, OP_MUL, OP_SUB};
-use crate::types::{Function, Num, Type};
+use crate::ast::{
+    self, AssignmentOperator, BinaryOperator, DeclarationKind, Declarator, FunctionDeclaration,
+    LogicalOperator, TemplateElement, UnaryOperator, UpdateOperator,
+};
+use crate::builtins;
+use crate::environment::{BindingError, Environment};
+use crate::error::{ErrorKind, JsError};
+use crate::lexer::Position;
+use crate::parser::Parser;
+use crate::types::{array_index, Function, FunctionBody, Num, Properties, Type, MAX_ARRAY_LENGTH};
 
-struct Interpreter<'a> {
-    scope_stack: Vec<Vec<char>>,
-    current: usize,
-    lookahead: usize,
-    value_table: HashMap<String, Type<'a>>,
-    function_table: HashMap<String, Function<'a>>,
+/// How a statement finished running.
+enum Completion {
+    Normal(Type),
+    /// A `return` was reached, ending the enclosing function call with this value.
+    Return(Type),
+    /// A `break`, with the label it targets if it has one.
+    Break(Option<String>),
+    Continue(Option<String>),
 }
 
-impl<'a> Interpreter<'a> {
-    pub fn new(source: Vec<char>) -> Self {
-        let mut scope_stack = vec![source];
+/// Decides whether a loop labelled with `labels` keeps going after its body completed with
+/// `completion`, or stops and completes with the returned value.
+fn loop_control(completion: Completion, labels: &[String]) -> ControlFlow<Completion> {
+    let targets =
+        |label: &Option<String>| label.as_ref().is_none_or(|label| labels.contains(label));
+    match completion {
+        Completion::Normal(_) => ControlFlow::Continue(()),
+        Completion::Continue(label) if targets(&label) => ControlFlow::Continue(()),
+        Completion::Break(label) if targets(&label) => {
+            ControlFlow::Break(Completion::Normal(Type::Undefined))
+        }
+        completion => ControlFlow::Break(completion),
+    }
+}
+
+/// Where an assignment or update stores its value, with any object and key already evaluated
+/// so that compound operators evaluate them only once.
+enum Reference {
+    Binding(String, Position),
+    Property(Type, String, Position),
+}
+
+impl Reference {
+    fn position(&self) -> Position {
+        match self {
+            Reference::Binding(_, position) | Reference::Property(_, _, position) => *position,
+        }
+    }
+}
+
+/// Which of an object's `valueOf` and `toString` ToPrimitive tries first. Without dates, the
+/// spec's default hint behaves the same as `Number`.
+#[derive(Clone, Copy)]
+enum Hint {
+    Number,
+    String,
+}
+
+/// How many script function calls may be nested before a `RangeError` is thrown instead of
+/// overflowing the Rust stack.
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// How deeply expressions and statements may be nested, counting those in every function call
+/// that's running, before a `RangeError` is thrown instead of overflowing the Rust stack. The
+/// parser applies the same limit to the code it reads.
+pub const MAX_NESTING_DEPTH: usize = 5000;
+
+/// A thread stack size with room for `MAX_CALL_DEPTH` nested calls and `MAX_NESTING_DEPTH`
+/// nested expressions and statements, even in a debug build.
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+pub struct Interpreter {
+    global: Environment,
+    /// The innermost scope of the code currently running.
+    environment: Environment,
+    /// How many script function calls are currently running.
+    depth: usize,
+    /// How many expressions and statements are currently being evaluated.
+    nesting: usize,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let global = Environment::global(builtins::globals());
         Self {
-            scope_stack,
-            current: 0,
-            lookahead: 0,
-            value_table: HashMap::new(),
-            function_table: HashMap::new(),
+            environment: global.clone(),
+            global,
+            depth: 0,
+            nesting: 0,
         }
     }
 
-    /// Returns the current scope.
-    fn scope(&self) -> &Vec<char> {
-        &self.scope_stack[self.scope_stack.len() - 1]
+    /// Parses `source` in full before running it and returns the value of the last statement.
+    pub fn eval(&mut self, source: &str) -> Result<Type, JsError> {
+        let program = Parser::new(source)?.program()?;
+        self.program(&program)
     }
 
-    /// Consumes the current character and returns it.
-    fn current(&mut self) -> char {
-        let current = self.scope()[self.current];
-        self.current += 1;
-        self.lookahead += 1;
-        current
+    pub fn get_global(&self, name: &str) -> Option<Type> {
+        self.global.get(name).ok()
     }
 
-    /// Gets the next character but doesn't consume it. Increments the lookahead counter.
-    fn lookahead(&self) -> char {
-        let lookahead = self.scope()[self.lookahead];
-        lookahead
+    pub fn set_global(&mut self, name: &str, value: Type) {
+        self.global.define(name, value);
     }
 
-    fn match_char(&mut self, char_to_match: char) {
-        let current = self.current();
-        if current != char_to_match {
-            panic!("Expected {} found {}", char_to_match, current);
-        }
+    /// Runs `run` with `environment` as the current scope, restoring the previous scope
+    /// afterwards whether or not it succeeds.
+    fn scoped<T>(
+        &mut self,
+        environment: Environment,
+        run: impl FnOnce(&mut Self) -> Result<T, JsError>,
+    ) -> Result<T, JsError> {
+        let previous = mem::replace(&mut self.environment, environment);
+        let result = run(self);
+        self.environment = previous;
+        result
     }
 
-    fn matches_char(&self, char_to_match: char) -> bool {
-        self.lookahead() == char_to_match
+    /// Runs `run` one level deeper in the script's expressions and statements, throwing a
+    /// `RangeError` instead once they're `MAX_NESTING_DEPTH` deep.
+    fn nested<T>(
+        &mut self,
+        run: impl FnOnce(&mut Self) -> Result<T, JsError>,
+    ) -> Result<T, JsError> {
+        if self.nesting == MAX_NESTING_DEPTH {
+            return Err(JsError::new(
+                ErrorKind::RangeError,
+                "Maximum call stack size exceeded",
+                None,
+            ));
+        }
+        self.nesting += 1;
+        let result = run(self);
+        self.nesting -= 1;
+        result
     }
+}
 
-    fn matches_any(&self, chars_to_match: &[char]) -> bool {
-        chars_to_match.contains(&self.lookahead())
+trait Expression {
+    fn expression(&mut self, expression: &ast::Expression) -> Result<Type, JsError>;
+    /// Evaluates `expression`. Callers go through `expression`, which counts the nesting.
+    fn evaluate(&mut self, expression: &ast::Expression) -> Result<Type, JsError>;
+    /// Creates the object an object literal describes, evaluating its properties in order.
+    fn object(&mut self, properties: &[ast::Property]) -> Result<Type, JsError>;
+    /// Evaluates `value`, naming it `name` if it's an anonymous function, so that `{ f() {} }`
+    /// and `{ f: () => {} }` both create a function called `f`.
+    fn named(&mut self, value: &ast::Expression, name: &str) -> Result<Type, JsError>;
+    fn term(&mut self, ident: &str, position: Position) -> Result<Type, JsError>;
+    /// The spec's ToPrimitive, which calls an object's own `valueOf` and `toString` in the
+    /// order `hint` asks for and returns the first primitive either gives.
+    fn primitive(&mut self, value: Type, hint: Hint, position: Position) -> Result<Type, JsError>;
+    /// The spec's ToNumber, converting objects with `primitive` first.
+    fn number(&mut self, value: Type, position: Position) -> Result<Num, JsError>;
+    /// The spec's ToString, converting objects with `primitive` first.
+    fn string(&mut self, value: Type, position: Position) -> Result<String, JsError>;
+    fn operation(
+        &mut self,
+        operator: BinaryOperator,
+        prev: Type,
+        this: Type,
+        position: Position,
+    ) -> Result<Type, JsError>;
+    fn unary(
+        &mut self,
+        operator: UnaryOperator,
+        operand: &ast::Expression,
+        position: Position,
+    ) -> Result<Type, JsError>;
+    /// The `delete` operator, which removes a property and reports whether it's now gone.
+    fn delete(&mut self, operand: &ast::Expression, position: Position) -> Result<Type, JsError>;
+    fn reference(&mut self, target: &ast::Expression) -> Result<Reference, JsError>;
+    fn get_value(&mut self, reference: &Reference) -> Result<Type, JsError>;
+    fn put_value(&mut self, reference: &Reference, value: Type) -> Result<(), JsError>;
+    fn member(&mut self, object: Type, property: &str, position: Position)
+        -> Result<Type, JsError>;
+    fn call(
+        &mut self,
+        callee: &ast::Expression,
+        arguments: &[ast::Expression],
+        position: Position,
+    ) -> Result<Type, JsError>;
+    /// Evaluates the function a call or tagged template refers to, along with the `this` it
+    /// should see.
+    fn callee(
+        &mut self,
+        callee: &ast::Expression,
+        position: Position,
+    ) -> Result<(Rc<Function>, Type), JsError>;
+    fn invoke(
+        &mut self,
+        function: &Function,
+        this: Type,
+        values: Vec<Type>,
+        position: Position,
+    ) -> Result<Type, JsError>;
+}
+
+/// A short description of `expression` for error messages, e.g. `console.log`.
+fn describe(expression: &ast::Expression) -> String {
+    match expression {
+        ast::Expression::Identifier(name, _) => name.clone(),
+        ast::Expression::Member(object, property, _) => {
+            format!("{}.{}", describe(object), property)
+        }
+        _ => String::from("expression"),
     }
+}
 
-    fn is_digit(&self) -> bool {
-        self.lookahead().is_digit(10)
+/// An object holding `values` under their indexes along with a `length`, like the
+/// `arguments` object a function body sees. Unlike a `Type::Array` it can hold other properties
+/// as well, which a tagged template's strings need for their `raw` copy.
+fn array_like(values: Vec<Type>) -> Type {
+    let mut object = Properties::new();
+    object.insert(
+        String::from("length"),
+        Type::Number(Num::F64(values.len() as f64)),
+    );
+    for (index, value) in values.into_iter().enumerate() {
+        object.insert(index.to_string(), value);
     }
+    Type::Object(Rc::new(RefCell::new(object)))
+}
 
-    fn is_alpha(&self) -> bool {
-        self.lookahead().is_alphabetic()
+/// The properties spreading `value` into an object literal copies. Only objects, arrays and
+/// strings have any.
+fn own_properties(value: &Type) -> Vec<(String, Type)> {
+    match value {
+        Type::Object(object) => object
+            .borrow()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+        Type::Array(array) => array
+            .borrow()
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (index.to_string(), value.clone()))
+            .collect(),
+        Type::TextString(string) => string
+            .encode_utf16()
+            .enumerate()
+            .map(|(index, unit)| {
+                let unit = String::from_utf16_lossy(&[unit]);
+                (index.to_string(), Type::TextString(unit))
+            })
+            .collect(),
+        _ => Vec::new(),
     }
+}
 
-    fn is_alphanum(&self) -> bool {
-        self.is_alpha() || self.is_digit()
+/// The first argument a tagged template passes its tag: the cooked strings, with the raw
+/// strings under `raw`.
+fn template_strings(strings: &[TemplateElement]) -> Type {
+    let cooked = strings
+        .iter()
+        .map(|string| match &string.cooked {
+            Some(cooked) => Type::TextString(cooked.clone()),
+            None => Type::Undefined,
+        })
+        .collect();
+    let raw = strings
+        .iter()
+        .map(|string| Type::TextString(string.raw.clone()))
+        .collect();
+    let strings = array_like(cooked);
+    if let Type::Object(object) = &strings {
+        object
+            .borrow_mut()
+            .insert(String::from("raw"), array_like(raw));
     }
+    strings
+}
+
+/// Creates a function value that runs `function` in a scope enclosed by `environment`.
+fn closure(function: &FunctionDeclaration, environment: Environment, arrow: bool) -> Type {
+    Type::Function(Rc::new(Function {
+        name: function.name.clone(),
+        body: FunctionBody::Script {
+            params: Rc::clone(&function.params),
+            body: Rc::clone(&function.body),
+            environment,
+            arrow,
+            source: Rc::clone(&function.source),
+        },
+    }))
+}
+
+/// The number a bitwise operator produces.
+fn int32(number: i32) -> Type {
+    Type::Number(Num::F64(number as f64))
+}
 
-    fn whitespace(&mut self) {
-        while self.lookahead() == ' ' {
-            self.match_char(' ');
+/// The `instanceof` operator. Objects can't be created with `new` yet, so nothing inherits from
+/// a function's prototype and the answer is always `false` once the operands are checked.
+fn instance_of(value: &Type, target: &Type, position: Position) -> Result<Type, JsError> {
+    let error = |message: &str| Err(JsError::type_error(message, position));
+    let function = match target {
+        Type::Function(function) => function,
+        Type::Object(_) | Type::Array(_) => {
+            return error("Right-hand side of 'instanceof' is not callable")
+        }
+        _ => return error("Right-hand side of 'instanceof' is not an object"),
+    };
+    let has_prototype = matches!(function.body, FunctionBody::Script { arrow: false, .. });
+    match value {
+        Type::Object(_) | Type::Array(_) | Type::Function(_) if !has_prototype => {
+            error("Function has non-object prototype 'undefined' in instanceof check")
         }
+        _ => Ok(Type::Boolean(false)),
     }
 }
 
-trait Expression<'a> {
-    fn expression(&mut self) -> Type<'a>;
-    fn term(&mut self) -> Type<'a>;
-    fn operation(&mut self, operator: char, prev: Type<'a>) -> Type<'a>;
-    fn add(&mut self, prev: Type<'a>) -> Type<'a>;
-    fn sub(&mut self, prev: Type<'a>) -> Type<'a>;
-    fn mul(&mut self, prev: Type<'a>) -> Type<'a>;
-    fn div(&mut self, prev: Type<'a>) -> Type<'a>;
-    fn factor(&mut self) -> Type<'a>;
-    fn ident(&mut self) -> String;
-    fn string(&mut self) -> String;
-    fn number(&mut self) -> f64;
+fn binding_error(error: BindingError, name: &str, position: Position) -> JsError {
+    match error {
+        BindingError::NotDefined => {
+            JsError::reference(format!("{} is not defined", name), position)
+        }
+        BindingError::Uninitialized => JsError::reference(
+            format!("Cannot access '{}' before initialization", name),
+            position,
+        ),
+        BindingError::Constant => JsError::type_error("Assignment to constant variable.", position),
+        BindingError::Redeclared => JsError::syntax(
+            format!("Identifier '{}' has already been declared", name),
+            position,
+        ),
+    }
 }
 
-impl<'a> Expression<'a> for Interpreter<'a> {
-    fn string(&mut self) -> String {
-        let mut string = String::new();
-        self.match_char('"');
-        while self.lookahead() != '"' {
-            string.push(self.current());
-        }
-        self.match_char('"');
-        string
+impl Expression for Interpreter {
+    fn expression(&mut self, expression: &ast::Expression) -> Result<Type, JsError> {
+        self.nested(|this| this.evaluate(expression))
     }
 
-    fn number(&mut self) -> f64 {
-        let mut number = String::new();
-        while self.is_digit() {
-            number.push(self.current());
+    fn evaluate(&mut self, expression: &ast::Expression) -> Result<Type, JsError> {
+        use ast::Expression::*;
+        match expression {
+            Number(number) => Ok(Type::Number(Num::F64(*number))),
+            String(string) => Ok(Type::TextString(string.clone())),
+            Boolean(boolean) => Ok(Type::Boolean(*boolean)),
+            Null => Ok(Type::Null),
+            Object(properties) => self.object(properties),
+            Array(elements) => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(self.expression(element)?);
+                }
+                Ok(Type::Array(Rc::new(RefCell::new(values))))
+            }
+            Identifier(ident, position) => self.term(ident, *position),
+            Binary(operator, left, right, position) => {
+                let prev = self.expression(left)?;
+                let this = self.expression(right)?;
+                self.operation(*operator, prev, this, *position)
+            }
+            Unary(operator, operand, position) => self.unary(*operator, operand, *position),
+            Update(operator, prefix, target, position) => {
+                let reference = self.reference(target)?;
+                let prev = self.get_value(&reference)?;
+                let prev = self.number(prev, *position)?;
+                let next = match operator {
+                    UpdateOperator::Increment => prev + Num::F64(1f64),
+                    UpdateOperator::Decrement => prev - Num::F64(1f64),
+                };
+                self.put_value(&reference, Type::Number(next))?;
+                Ok(Type::Number(if *prefix { next } else { prev }))
+            }
+            Logical(operator, left, right) => {
+                let left = self.expression(left)?;
+                let short_circuits = match operator {
+                    LogicalOperator::And => !left.is_truthy(),
+                    LogicalOperator::Or => left.is_truthy(),
+                    LogicalOperator::Nullish => !matches!(left, Type::Null | Type::Undefined),
+                };
+                if short_circuits {
+                    Ok(left)
+                } else {
+                    self.expression(right)
+                }
+            }
+            Conditional(test, consequent, alternate) => {
+                if self.expression(test)?.is_truthy() {
+                    self.expression(consequent)
+                } else {
+                    self.expression(alternate)
+                }
+            }
+            Assign(operator, target, value, _) => {
+                let reference = self.reference(target)?;
+                let value = match operator {
+                    AssignmentOperator::Assign => self.expression(value)?,
+                    AssignmentOperator::Binary(operator) => {
+                        let prev = self.get_value(&reference)?;
+                        let this = self.expression(value)?;
+                        self.operation(*operator, prev, this, reference.position())?
+                    }
+                    AssignmentOperator::Logical(operator) => {
+                        let prev = self.get_value(&reference)?;
+                        let short_circuits = match operator {
+                            LogicalOperator::And => !prev.is_truthy(),
+                            LogicalOperator::Or => prev.is_truthy(),
+                            LogicalOperator::Nullish => {
+                                !matches!(prev, Type::Null | Type::Undefined)
+                            }
+                        };
+                        // `a ||= b` doesn't assign at all when `a` is already truthy.
+                        if short_circuits {
+                            return Ok(prev);
+                        }
+                        self.expression(value)?
+                    }
+                };
+                self.put_value(&reference, value.clone())?;
+                Ok(value)
+            }
+            Sequence(expressions) => {
+                let mut value = Type::Undefined;
+                for expression in expressions {
+                    value = self.expression(expression)?;
+                }
+                Ok(value)
+            }
+            Member(object, property, position) => {
+                let object = self.expression(object)?;
+                self.member(object, property, *position)
+            }
+            Index(object, property, position) => {
+                let object = self.expression(object)?;
+                let property = self.expression(property)?;
+                let property = self.string(property, *position)?;
+                self.member(object, &property, *position)
+            }
+            Call(callee, arguments, position) => self.call(callee, arguments, *position),
+            Template(strings, substitutions, position) => {
+                let mut text = Vec::new();
+                for (index, string) in strings.iter().enumerate() {
+                    text.push(string.cooked.clone().unwrap_or_default());
+                    if let Some(substitution) = substitutions.get(index) {
+                        let substitution = self.expression(substitution)?;
+                        text.push(self.string(substitution, *position)?);
+                    }
+                }
+                Ok(Type::TextString(text.concat()))
+            }
+            TaggedTemplate(tag, strings, substitutions, position) => {
+                let (function, this) = self.callee(tag, *position)?;
+                let mut values = vec![template_strings(strings)];
+                for substitution in substitutions {
+                    values.push(self.expression(substitution)?);
+                }
+                self.invoke(&function, this, values, *position)
+            }
+            Function(function) => {
+                // A named function expression can refer to itself by name, but the name
+                // isn't visible outside it.
+                let environment = match function.name.as_str() {
+                    "" => self.environment.clone(),
+                    _ => self.environment.block(),
+                };
+                let value = closure(function, environment.clone(), false);
+                if !function.name.is_empty() {
+                    environment.define(&function.name, value.clone());
+                }
+                Ok(value)
+            }
+            Arrow(function) => Ok(closure(function, self.environment.clone(), true)),
+            // Only ordinary function calls bind `this`, so it is undefined at the top level.
+            This => Ok(self.environment.get("this").unwrap_or(Type::Undefined)),
         }
-        number.parse().unwrap()
     }
 
-    fn ident(&mut self) -> String {
-        let mut ident = String::new();
-        while self.is_alphanum() {
-            ident.push(self.current());
+    fn term(&mut self, ident: &str, position: Position) -> Result<Type, JsError> {
+        match ident {
+            "undefined" => Ok(Type::Undefined),
+            id => self
+                .environment
+                .get(id)
+                .map_err(|error| binding_error(error, id, position)),
         }
-        self.whitespace();
-        ident
     }
 
-    fn expression(&mut self) -> Type<'a> {
-        let mut prev = self.factor();
-        while [OP_ADD, OP_SUB].contains(&self.lookahead()) {
-            prev = match self.lookahead() {
-                OP_ADD => self.add(prev),
-                OP_SUB => self.sub(prev),
-                _ => prev,
+    fn primitive(&mut self, value: Type, hint: Hint, position: Position) -> Result<Type, JsError> {
+        // Arrays and functions can't have a `valueOf` or `toString` of their own.
+        let object = match &value {
+            Type::Object(object) => object.clone(),
+            value => return Ok(value.to_primitive()),
+        };
+        let methods = match hint {
+            Hint::Number => ["valueOf", "toString"],
+            Hint::String => ["toString", "valueOf"],
+        };
+        for method in methods {
+            let function = object.borrow().get(method).cloned();
+            let result = match function {
+                Some(Type::Function(function)) => {
+                    self.invoke(&function, value.clone(), Vec::new(), position)?
+                }
+                // Without prototypes, an object that doesn't define them behaves as if it had
+                // the built-in ones: `toString` gives `[object Object]` and `valueOf` gives the
+                // object back.
+                None if method == "toString" => value.to_primitive(),
+                _ => continue,
             };
+            if !matches!(result, Type::Object(_) | Type::Array(_) | Type::Function(_)) {
+                return Ok(result);
+            }
         }
+        Err(JsError::type_error(
+            "Cannot convert object to primitive value",
+            position,
+        ))
+    }
 
-        prev
+    fn number(&mut self, value: Type, position: Position) -> Result<Num, JsError> {
+        Ok(self.primitive(value, Hint::Number, position)?.to_number())
     }
 
-    fn factor(&mut self) -> Type<'a> {
-        let mut prev = Type::Undefined;
-        if self.matches_char('(') {
-            self.match_char('(');
-            prev = self.expression();
-            self.match_char(')');
-        } else {
-            prev = self.term();
-        }
-        prev = match self.lookahead() {
-            OP_MUL => self.mul(prev),
-            OP_DIV => self.div(prev),
-            _ => prev,
+    fn string(&mut self, value: Type, position: Position) -> Result<String, JsError> {
+        Ok(self.primitive(value, Hint::String, position)?.to_string())
+    }
+
+    fn operation(
+        &mut self,
+        operator: BinaryOperator,
+        prev: Type,
+        this: Type,
+        position: Position,
+    ) -> Result<Type, JsError> {
+        use BinaryOperator::*;
+        let object =
+            |value: &Type| matches!(value, Type::Object(_) | Type::Array(_) | Type::Function(_));
+        let (prev, this) = match operator {
+            StrictEq | StrictNotEq | InstanceOf => (prev, this),
+            In => (self.primitive(prev, Hint::String, position)?, this),
+            // `==` only converts an object compared with a primitive other than null and
+            // undefined.
+            LooseEq | LooseNotEq => match (object(&prev), object(&this)) {
+                (true, false) if !matches!(this, Type::Null | Type::Undefined) => {
+                    (self.primitive(prev, Hint::Number, position)?, this)
+                }
+                (false, true) if !matches!(prev, Type::Null | Type::Undefined) => {
+                    (prev, self.primitive(this, Hint::Number, position)?)
+                }
+                _ => (prev, this),
+            },
+            _ => {
+                let prev = self.primitive(prev, Hint::Number, position)?;
+                (prev, self.primitive(this, Hint::Number, position)?)
+            }
         };
+        Ok(match operator {
+            Add => prev + this,
+            Sub => prev - this,
+            Mul => prev * this,
+            Div => prev / this,
+            Exp => Type::Number(prev.to_number().pow(this.to_number())),
+            Lt => Type::Boolean(prev.less_than(&this) == Some(true)),
+            Gt => Type::Boolean(this.less_than(&prev) == Some(true)),
+            LtEq => Type::Boolean(this.less_than(&prev) == Some(false)),
+            GtEq => Type::Boolean(prev.less_than(&this) == Some(false)),
+            LooseEq => Type::Boolean(prev.loose_equals(&this)),
+            LooseNotEq => Type::Boolean(!prev.loose_equals(&this)),
+            StrictEq => Type::Boolean(prev.strict_equals(&this)),
+            StrictNotEq => Type::Boolean(!prev.strict_equals(&this)),
+            In => match this {
+                Type::Object(object) => {
+                    Type::Boolean(object.borrow().contains_key(&prev.to_string()))
+                }
+                Type::Array(array) => {
+                    let key = prev.to_string();
+                    let length = array.borrow().len();
+                    Type::Boolean(key == "length" || array_index(&key).is_some_and(|i| i < length))
+                }
+                Type::Function(_) => Type::Boolean(false),
+                _ => {
+                    return Err(JsError::type_error(
+                        format!(
+                            "Cannot use 'in' operator to search for '{}' in {}",
+                            prev, this
+                        ),
+                        position,
+                    ))
+                }
+            },
+            Mod => Type::Number(prev.to_number() % this.to_number()),
+            BitAnd => int32(prev.to_number().to_int32() & this.to_number().to_int32()),
+            BitOr => int32(prev.to_number().to_int32() | this.to_number().to_int32()),
+            BitXor => int32(prev.to_number().to_int32() ^ this.to_number().to_int32()),
+            // Shift counts only use their lowest five bits, so shifting by 32 does nothing.
+            Shl => int32(
+                prev.to_number()
+                    .to_int32()
+                    .wrapping_shl(this.to_number().to_uint32()),
+            ),
+            Shr => int32(
+                prev.to_number()
+                    .to_int32()
+                    .wrapping_shr(this.to_number().to_uint32()),
+            ),
+            UShr => Type::Number(Num::F64(
+                prev.to_number()
+                    .to_uint32()
+                    .wrapping_shr(this.to_number().to_uint32()) as f64,
+            )),
+            InstanceOf => instance_of(&prev, &this, position)?,
+        })
+    }
 
-        prev
-    }
-
-    fn term(&mut self) -> Type<'a> {
-        let mut ret = Type::Undefined;
-        if self.is_digit() {
-            ret = Type::Number(Num::F64(self.number()));
-        } else if self.matches_any(&['"', '\'']) {
-            ret = Type::TextString(self.string());
-        } else if self.is_alpha() {
-            let ident = self.ident();
-            match &ident[..] {
-                "undefined" => ret = Type::Undefined,
-                id => {
-                    let value = &self.value_table[id];
-                    match value {
-                        Type::Function(name) => {
-                            // evaluate function and return result
-                        }
-                        _ => ret = value.clone(),
+    fn unary(
+        &mut self,
+        operator: UnaryOperator,
+        operand: &ast::Expression,
+        position: Position,
+    ) -> Result<Type, JsError> {
+        let mut number = |operand| {
+            let value = self.expression(operand)?;
+            self.number(value, position)
+        };
+        Ok(match operator {
+            UnaryOperator::Minus => Type::Number(-number(operand)?),
+            UnaryOperator::Plus => Type::Number(number(operand)?),
+            UnaryOperator::BitNot => int32(!number(operand)?.to_int32()),
+            UnaryOperator::Not => Type::Boolean(!self.expression(operand)?.is_truthy()),
+            UnaryOperator::Typeof => {
+                let value = match operand {
+                    // `typeof` is the one place an undeclared name isn't an error.
+                    ast::Expression::Identifier(name, _)
+                        if matches!(self.environment.get(name), Err(BindingError::NotDefined)) =>
+                    {
+                        Type::Undefined
                     }
+                    operand => self.expression(operand)?,
+                };
+                Type::TextString(String::from(value.type_of()))
+            }
+            UnaryOperator::Void => {
+                self.expression(operand)?;
+                Type::Undefined
+            }
+            UnaryOperator::Delete => return self.delete(operand, position),
+        })
+    }
+
+    fn delete(&mut self, operand: &ast::Expression, position: Position) -> Result<Type, JsError> {
+        let (object, property) = match operand {
+            ast::Expression::Member(object, property, _) => {
+                (self.expression(object)?, property.clone())
+            }
+            ast::Expression::Index(object, property, _) => {
+                let object = self.expression(object)?;
+                let property = self.expression(property)?;
+                (object, self.string(property, position)?)
+            }
+            // Declared variables can't be deleted, and deleting anything that isn't a reference
+            // just evaluates it.
+            ast::Expression::Identifier(name, _) => {
+                return Ok(Type::Boolean(self.environment.get(name).is_err()))
+            }
+            operand => {
+                self.expression(operand)?;
+                return Ok(Type::Boolean(true));
+            }
+        };
+        match object {
+            Type::Object(object) => {
+                object.borrow_mut().remove(&property);
+                Ok(Type::Boolean(true))
+            }
+            // Without holes, a deleted element is left as `undefined`, and `length` stays.
+            Type::Array(array) => {
+                let mut array = array.borrow_mut();
+                if let Some(element) = array_index(&property).and_then(|i| array.get_mut(i)) {
+                    *element = Type::Undefined;
                 }
-            };
+                Ok(Type::Boolean(property != "length"))
+            }
+            Type::Null | Type::Undefined => Err(JsError::type_error(
+                format!(
+                    "Cannot convert undefined or null to object (deleting '{}')",
+                    property
+                ),
+                position,
+            )),
+            _ => Ok(Type::Boolean(true)),
+        }
+    }
+
+    fn object(&mut self, properties: &[ast::Property]) -> Result<Type, JsError> {
+        let mut object = Properties::new();
+        for property in properties {
+            match property {
+                ast::Property::Init(key, value) => {
+                    let key = match key {
+                        ast::PropertyKey::Named(name) => name.clone(),
+                        ast::PropertyKey::Computed(key, position) => {
+                            let key = self.expression(key)?;
+                            self.string(key, *position)?
+                        }
+                    };
+                    let value = self.named(value, &key)?;
+                    object.insert(key, value);
+                }
+                ast::Property::Spread(value) => {
+                    let value = self.expression(value)?;
+                    object.extend(own_properties(&value));
+                }
+            }
+        }
+        Ok(Type::Object(Rc::new(RefCell::new(object))))
+    }
+
+    fn named(&mut self, value: &ast::Expression, name: &str) -> Result<Type, JsError> {
+        match value {
+            ast::Expression::Function(function) | ast::Expression::Arrow(function)
+                if function.name.is_empty() =>
+            {
+                let function = FunctionDeclaration {
+                    name: String::from(name),
+                    ..function.clone()
+                };
+                let arrow = matches!(value, ast::Expression::Arrow(_));
+                Ok(closure(&function, self.environment.clone(), arrow))
+            }
+            value => self.expression(value),
         }
+    }
 
-        self.whitespace();
+    fn reference(&mut self, target: &ast::Expression) -> Result<Reference, JsError> {
+        match target {
+            ast::Expression::Identifier(name, position) => {
+                Ok(Reference::Binding(name.clone(), *position))
+            }
+            ast::Expression::Member(object, property, position) => {
+                let object = self.expression(object)?;
+                Ok(Reference::Property(object, property.clone(), *position))
+            }
+            ast::Expression::Index(object, property, position) => {
+                let object = self.expression(object)?;
+                let property = self.expression(property)?;
+                let property = self.string(property, *position)?;
+                Ok(Reference::Property(object, property, *position))
+            }
+            // The parser only produces assignments and updates to the targets above.
+            _ => unreachable!("invalid assignment target"),
+        }
+    }
 
-        ret
+    fn get_value(&mut self, reference: &Reference) -> Result<Type, JsError> {
+        match reference {
+            Reference::Binding(name, position) => self.term(name, *position),
+            Reference::Property(object, property, position) => {
+                self.member(object.clone(), property, *position)
+            }
+        }
     }
 
-    fn operation(&mut self, operator: char, prev: Type<'a>) -> Type<'a> {
-        self.match_char(operator);
-        self.whitespace();
-        let this = self.factor();
-        match operator {
-            OP_ADD => prev + this,
-            OP_SUB => prev - this,
-            OP_MUL => prev * this,
-            OP_DIV => prev / this,
-            _ => Type::Undefined,
+    fn put_value(&mut self, reference: &Reference, value: Type) -> Result<(), JsError> {
+        match reference {
+            Reference::Binding(name, position) => {
+                match self.environment.assign(name, value.clone()) {
+                    Ok(()) => Ok(()),
+                    // Assigning to an undeclared name creates a global, as in sloppy mode.
+                    Err(BindingError::NotDefined) => {
+                        self.global.define(name, value);
+                        Ok(())
+                    }
+                    Err(error) => Err(binding_error(error, name, *position)),
+                }
+            }
+            Reference::Property(object, property, position) => match object {
+                Type::Object(object) => {
+                    object.borrow_mut().insert(property.clone(), value);
+                    Ok(())
+                }
+                Type::Array(array) => {
+                    // Converting the length may call back into the script, so it happens
+                    // before the array is borrowed.
+                    let length = match property.as_str() {
+                        "length" => Some(self.number(value.clone(), *position)?),
+                        _ => None,
+                    };
+                    let mut array = array.borrow_mut();
+                    let invalid = || {
+                        JsError::new(
+                            ErrorKind::RangeError,
+                            "Invalid array length",
+                            Some(*position),
+                        )
+                    };
+                    if let Some(number) = length {
+                        let length = number.to_uint32();
+                        if number != Num::F64(f64::from(length))
+                            || length as usize > MAX_ARRAY_LENGTH
+                        {
+                            return Err(invalid());
+                        }
+                        array.resize(length as usize, Type::Undefined);
+                    } else if let Some(index) = array_index(property) {
+                        if index >= MAX_ARRAY_LENGTH {
+                            return Err(invalid());
+                        }
+                        if index >= array.len() {
+                            array.resize(index + 1, Type::Undefined);
+                        }
+                        array[index] = value;
+                    } else {
+                        // Arrays only hold their elements, so there's nowhere to keep any
+                        // other property.
+                        return Err(JsError::type_error(
+                            format!("Cannot create property '{}' on an array", property),
+                            *position,
+                        ));
+                    }
+                    Ok(())
+                }
+                Type::Null | Type::Undefined => Err(JsError::type_error(
+                    format!(
+                        "Cannot set properties of {} (setting '{}')",
+                        object, property
+                    ),
+                    *position,
+                )),
+                // Primitives and functions have nowhere to store the property, so the
+                // assignment is silently dropped.
+                _ => Ok(()),
+            },
         }
     }
 
-    fn add(&mut self, prev: Type<'a>) -> Type<'a> {
-        println!("add");
-        self.operation(OP_ADD, prev)
+    fn member(
+        &mut self,
+        object: Type,
+        property: &str,
+        position: Position,
+    ) -> Result<Type, JsError> {
+        match object {
+            Type::Object(object) => Ok(object
+                .borrow()
+                .get(property)
+                .cloned()
+                .unwrap_or(Type::Undefined)),
+            Type::Array(array) => {
+                let array = array.borrow();
+                Ok(match property {
+                    "length" => Type::Number(Num::F64(array.len() as f64)),
+                    property => array_index(property)
+                        .and_then(|index| array.get(index).cloned())
+                        .unwrap_or(Type::Undefined),
+                })
+            }
+            // Strings are indexed by UTF-16 code unit, like their `length`.
+            Type::TextString(string) => Ok(match property {
+                "length" => Type::Number(Num::F64(string.encode_utf16().count() as f64)),
+                property => array_index(property)
+                    .and_then(|index| string.encode_utf16().nth(index))
+                    .map_or(Type::Undefined, |unit| {
+                        Type::TextString(String::from_utf16_lossy(&[unit]))
+                    }),
+            }),
+            Type::Function(function) if property == "name" => {
+                Ok(Type::TextString(function.name.clone()))
+            }
+            Type::Null | Type::Undefined => Err(JsError::type_error(
+                format!(
+                    "Cannot read properties of {} (reading '{}')",
+                    object, property
+                ),
+                position,
+            )),
+            // Numbers and booleans have no properties of their own, and there are no prototypes
+            // yet for them to inherit methods from.
+            _ => Ok(Type::Undefined),
+        }
     }
 
-    fn sub(&mut self, prev: Type<'a>) -> Type<'a> {
-        println!("sub");
-        self.operation(OP_SUB, prev)
+    fn call(
+        &mut self,
+        callee: &ast::Expression,
+        arguments: &[ast::Expression],
+        position: Position,
+    ) -> Result<Type, JsError> {
+        let (function, this) = self.callee(callee, position)?;
+        let mut values = Vec::new();
+        for argument in arguments {
+            values.push(self.expression(argument)?);
+        }
+        self.invoke(&function, this, values, position)
     }
 
-    fn mul(&mut self, prev: Type<'a>) -> Type<'a> {
-        self.operation(OP_MUL, prev)
+    fn callee(
+        &mut self,
+        callee: &ast::Expression,
+        position: Position,
+    ) -> Result<(Rc<Function>, Type), JsError> {
+        let (function, this) = match callee {
+            ast::Expression::Member(object, property, position) => {
+                let object = self.expression(object)?;
+                (self.member(object.clone(), property, *position)?, object)
+            }
+            ast::Expression::Index(object, property, position) => {
+                let object = self.expression(object)?;
+                let property = self.expression(property)?;
+                let property = self.string(property, *position)?;
+                (self.member(object.clone(), &property, *position)?, object)
+            }
+            callee => (self.expression(callee)?, Type::Undefined),
+        };
+        let function = match function {
+            Type::Function(function) => function,
+            _ => {
+                return Err(JsError::type_error(
+                    format!("{} is not a function", describe(callee)),
+                    position,
+                ))
+            }
+        };
+        Ok((function, this))
     }
 
-    fn div(&mut self, prev: Type<'a>) -> Type<'a> {
-        self.operation(OP_DIV, prev)
+    fn invoke(
+        &mut self,
+        function: &Function,
+        this: Type,
+        values: Vec<Type>,
+        position: Position,
+    ) -> Result<Type, JsError> {
+        match &function.body {
+            FunctionBody::Script {
+                params,
+                body,
+                environment,
+                arrow,
+                ..
+            } => {
+                let environment = environment.function();
+                for (index, param) in params.iter().enumerate() {
+                    let value = values.get(index).cloned().unwrap_or(Type::Undefined);
+                    environment.define(param, value);
+                }
+                if !arrow {
+                    environment.define("this", this);
+                    environment.define("arguments", array_like(values));
+                }
+                if self.depth == MAX_CALL_DEPTH {
+                    return Err(JsError::new(
+                        ErrorKind::RangeError,
+                        "Maximum call stack size exceeded",
+                        Some(position),
+                    ));
+                }
+                self.depth += 1;
+                let result = self.scoped(environment, |this| {
+                    this.hoist(body)?;
+                    match this.statements(body)? {
+                        Completion::Return(value) => Ok(value),
+                        _ => Ok(Type::Undefined),
+                    }
+                });
+                self.depth -= 1;
+                result
+            }
+            FunctionBody::Native(native) => native(&values, &this).map_err(|mut error| {
+                error.position = error.position.or(Some(position));
+                error
+            }),
+        }
     }
 }
 
-trait Assign {
-    fn function(&mut self);
-    fn assign(&mut self);
+trait Statement {
+    fn statement(&mut self, statement: &ast::Statement) -> Result<Completion, JsError>;
+    /// Runs `statement`. Callers go through `statement`, which counts the nesting.
+    fn execute(&mut self, statement: &ast::Statement) -> Result<Completion, JsError>;
+    fn statements(&mut self, body: &[ast::Statement]) -> Result<Completion, JsError>;
+    fn hoist(&mut self, body: &[ast::Statement]) -> Result<(), JsError>;
+    fn iteration(
+        &mut self,
+        statement: &ast::Statement,
+        labels: &[String],
+    ) -> Result<Completion, JsError>;
+    fn declaration(
+        &mut self,
+        kind: DeclarationKind,
+        declarators: &[Declarator],
+    ) -> Result<(), JsError>;
+    fn function(&mut self, function: &FunctionDeclaration) -> Result<(), JsError>;
 }
 
-impl<'a> Assign for Interpreter<'a> {
-    fn function(&mut self) {
-        if self.matches_char('f') {
-            let ident = self.ident();
-            match &ident[..] {
-                KW_FUNCTION => {
-                    self.whitespace();
-                    let name = self.ident();
-                    self.match_char('(');
-                    self.match_char(')');
-                    self.whitespace();
-                    self.match_char('{');
-                    self.match_char('\n');
-                    let mut code_block = String::new();
-                    while self.lookahead() != '}' {
-                        code_block.push(self.current());
+impl Statement for Interpreter {
+    fn statement(&mut self, statement: &ast::Statement) -> Result<Completion, JsError> {
+        self.nested(|this| this.execute(statement))
+    }
+
+    fn execute(&mut self, statement: &ast::Statement) -> Result<Completion, JsError> {
+        use ast::Statement::*;
+        match statement {
+            Declaration(kind, declarators) => self.declaration(*kind, declarators)?,
+            // Declared up front when the enclosing block or function is entered. A block function
+            // that sloppy mode hoists is copied out to its function's `var` once its block
+            // reaches it; a `let` or `const` an earlier script declared keeps that name.
+            Function(function) if function.annex_b => {
+                let value = self
+                    .environment
+                    .get(&function.name)
+                    .unwrap_or(Type::Undefined);
+                let _ = self
+                    .environment
+                    .function_scope()
+                    .declare(&function.name, value);
+            }
+            Function(_) => (),
+            Expression(expression) => return Ok(Completion::Normal(self.expression(expression)?)),
+            Block(body) => {
+                return self.scoped(self.environment.block(), |this| this.statements(body))
+            }
+            If(test, consequent, alternate) => {
+                if self.expression(test)?.is_truthy() {
+                    return self.statement(consequent);
+                }
+                if let Some(alternate) = alternate {
+                    return self.statement(alternate);
+                }
+            }
+            While(..) | DoWhile(..) | For(..) => return self.iteration(statement, &[]),
+            Break(label) => return Ok(Completion::Break(label.clone())),
+            Continue(label) => return Ok(Completion::Continue(label.clone())),
+            Labeled(..) => {
+                // Collect every label on the statement, as in `a: b: while (…)`.
+                let mut labels = Vec::new();
+                let mut body = statement;
+                while let Labeled(label, inner) = body {
+                    labels.push(label.clone());
+                    body = inner;
+                }
+                let completion = match body {
+                    While(..) | DoWhile(..) | For(..) => self.iteration(body, &labels)?,
+                    body => self.statement(body)?,
+                };
+                return Ok(match completion {
+                    Completion::Break(Some(label)) if labels.contains(&label) => {
+                        Completion::Normal(Type::Undefined)
+                    }
+                    completion => completion,
+                });
+            }
+            Return(value) => {
+                let value = match value {
+                    Some(value) => self.expression(value)?,
+                    None => Type::Undefined,
+                };
+                return Ok(Completion::Return(value));
+            }
+            Empty => (),
+        }
+        Ok(Completion::Normal(Type::Undefined))
+    }
+
+    /// Runs `body` in the current scope, declaring its functions and its `let` and `const`
+    /// bindings first. Stops early at a `return`.
+    fn statements(&mut self, body: &[ast::Statement]) -> Result<Completion, JsError> {
+        for statement in body {
+            match statement {
+                ast::Statement::Function(function) => self.function(function)?,
+                ast::Statement::Declaration(kind, declarators) if *kind != DeclarationKind::Var => {
+                    for Declarator { name, position, .. } in declarators {
+                        self.environment
+                            .declare_uninitialized(name, *kind == DeclarationKind::Let)
+                            .map_err(|error| binding_error(error, name, *position))?;
                     }
-                    let function = Function {
-                        arguments: HashMap::new(),
-                        body: code_block,
-                    };
-                    self.function_table.insert(name, function);
                 }
                 _ => (),
             }
-        } else if self.matches_char('(') {
         }
+        let mut value = Type::Undefined;
+        for statement in body {
+            match self.statement(statement)? {
+                Completion::Normal(next) => value = next,
+                completion => return Ok(completion),
+            }
+        }
+        Ok(Completion::Normal(value))
     }
 
-    fn assign(&mut self) {
-        let keyword = self.ident();
-        match &keyword[..] {
-            KW_VAR => {
-                let id = self.ident();
-                self.match_char(OP_EQ);
-                self.whitespace();
-                let value = self.expression();
-                self.value_table.insert(id, value);
+    /// Declares every `var` in `body`, including those nested in blocks, as `undefined` in
+    /// the current function scope, along with the block functions sloppy mode hoists.
+    fn hoist(&mut self, body: &[ast::Statement]) -> Result<(), JsError> {
+        let scope = self.environment.function_scope();
+        for statement in body {
+            match statement {
+                ast::Statement::Declaration(DeclarationKind::Var, declarators) => {
+                    for Declarator { name, position, .. } in declarators {
+                        scope
+                            .declare_hoisted(name)
+                            .map_err(|error| binding_error(error, name, *position))?;
+                    }
+                }
+                // As above, a `let` or `const` an earlier script declared takes precedence.
+                ast::Statement::Function(function) if function.annex_b => {
+                    let _ = scope.declare_hoisted(&function.name);
+                }
+                ast::Statement::Block(body) => self.hoist(body)?,
+                ast::Statement::If(_, consequent, alternate) => {
+                    self.hoist(slice::from_ref(consequent))?;
+                    if let Some(alternate) = alternate {
+                        self.hoist(slice::from_ref(alternate))?;
+                    }
+                }
+                ast::Statement::While(_, body)
+                | ast::Statement::DoWhile(body, _)
+                | ast::Statement::Labeled(_, body) => self.hoist(slice::from_ref(body))?,
+                ast::Statement::For(init, _, _, body) => {
+                    if let Some(init) = init {
+                        self.hoist(slice::from_ref(init))?;
+                    }
+                    self.hoist(slice::from_ref(body))?;
+                }
+                _ => (),
             }
-            KW_CONST => {
-                let id = self.ident();
-                self.match_char(OP_EQ);
-                self.whitespace();
-                let value = self.expression();
-                self.value_table.insert(id, value);
+        }
+        Ok(())
+    }
+
+    fn iteration(
+        &mut self,
+        statement: &ast::Statement,
+        labels: &[String],
+    ) -> Result<Completion, JsError> {
+        match statement {
+            ast::Statement::While(test, body) => {
+                while self.expression(test)?.is_truthy() {
+                    if let ControlFlow::Break(completion) =
+                        loop_control(self.statement(body)?, labels)
+                    {
+                        return Ok(completion);
+                    }
+                }
             }
-            KW_LET => {
-                let id = self.ident();
-                self.match_char(OP_EQ);
-                self.whitespace();
-                let value = self.expression();
-                self.value_table.insert(id, value);
+            ast::Statement::DoWhile(body, test) => loop {
+                if let ControlFlow::Break(completion) = loop_control(self.statement(body)?, labels)
+                {
+                    return Ok(completion);
+                }
+                if !self.expression(test)?.is_truthy() {
+                    break;
+                }
+            },
+            ast::Statement::For(init, test, update, body) => {
+                return self.scoped(self.environment.block(), |this| {
+                    if let Some(init) = init {
+                        this.statements(slice::from_ref(init))?;
+                    }
+                    // Closures created in one iteration keep that iteration's copy of any
+                    // `let` declared in the head.
+                    this.environment = this.environment.copy();
+                    loop {
+                        if let Some(test) = test {
+                            if !this.expression(test)?.is_truthy() {
+                                break;
+                            }
+                        }
+                        if let ControlFlow::Break(completion) =
+                            loop_control(this.statement(body)?, labels)
+                        {
+                            return Ok(completion);
+                        }
+                        this.environment = this.environment.copy();
+                        if let Some(update) = update {
+                            this.expression(update)?;
+                        }
+                    }
+                    Ok(Completion::Normal(Type::Undefined))
+                });
             }
-            id => {
-                self.match_char(OP_EQ);
-                self.whitespace();
-                let value = self.expression();
-                self.value_table.insert(String::from(id), value);
+            _ => unreachable!("not a loop"),
+        }
+        Ok(Completion::Normal(Type::Undefined))
+    }
+
+    fn declaration(
+        &mut self,
+        kind: DeclarationKind,
+        declarators: &[Declarator],
+    ) -> Result<(), JsError> {
+        for Declarator {
+            name,
+            init,
+            position,
+        } in declarators
+        {
+            let value = match init {
+                Some(init) => self.expression(init)?,
+                // `var a;` leaves a hoisted `a` as it was.
+                None if kind == DeclarationKind::Var => continue,
+                None => Type::Undefined,
+            };
+            match kind {
+                DeclarationKind::Var => self
+                    .environment
+                    .function_scope()
+                    .declare(name, value)
+                    .map_err(|error| binding_error(error, name, *position))?,
+                _ => self.environment.initialize(name, value),
             }
         }
+        Ok(())
+    }
+
+    fn function(&mut self, function: &FunctionDeclaration) -> Result<(), JsError> {
+        let value = closure(function, self.environment.clone(), false);
+        self.environment
+            .declare(&function.name, value)
+            .map_err(|error| binding_error(error, &function.name, function.position))
     }
 }
 
 trait Program {
-    fn program(&mut self);
+    fn program(&mut self, program: &ast::Program) -> Result<Type, JsError>;
 }
 
-impl<'a> Program for Interpreter<'a> {
-    fn program(&mut self) {
-        loop {
-            self.assign();
-            if self.matches_char(';') {
-                self.match_char(';');
-            }
-            if self.current >= self.scope_stack.last().unwrap().len() {
-                break;
-            }
-            if self.matches_char('\n') {
-                self.match_char('\n');
-            }
-            self.whitespace();
+impl Program for Interpreter {
+    fn program(&mut self, program: &ast::Program) -> Result<Type, JsError> {
+        self.hoist(&program.body)?;
+        match self.statements(&program.body)? {
+            Completion::Normal(value) | Completion::Return(value) => Ok(value),
+            // The parser rejects jumps that don't target an enclosing statement.
+            Completion::Break(_) | Completion::Continue(_) => Ok(Type::Undefined),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_support {
+    use super::*;
+
+    pub fn eval(source: &str) -> Type {
+        Interpreter::new().eval(source).unwrap()
+    }
+
+    pub fn error(source: &str) -> JsError {
+        Interpreter::new().eval(source).unwrap_err()
+    }
+
+    /// How `console.log` would show the value of `source`.
+    pub fn inspect(source: &str) -> String {
+        eval(source).inspect()
+    }
+
+    pub fn number(source: &str) -> f64 {
+        number_in(&mut Interpreter::new(), source)
+    }
+
+    /// Runs `source` in an existing interpreter, so it can see what earlier calls declared.
+    pub fn number_in(interpreter: &mut Interpreter, source: &str) -> f64 {
+        match interpreter.eval(source).unwrap() {
+            Type::Number(Num::F64(number)) => number,
+            actual => panic!("Expected number found {:?}", actual),
         }
     }
 }
@@ -306,8 +1273,8 @@ mod expression_tests {
     #[test]
     fn term_string() {
         let code = "\"jake\";";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.term();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::TextString(string) => {
                 assert_eq!(string, "jake");
@@ -319,9 +1286,8 @@ mod expression_tests {
     #[test]
     fn term_number() {
         let code = "12;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.term();
-        println!("{:?}", result);
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(number)) => {
                 assert_eq!(number, 12f64);
@@ -333,11 +1299,9 @@ mod expression_tests {
     #[test]
     fn term_variable() {
         let code = "myVar;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        interpreter
-            .value_table
-            .insert(String::from("myVar"), Type::Number(Num::F64(10f64)));
-        let result = interpreter.term();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("myVar", Type::Number(Num::F64(10f64)));
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(number)) => {
                 assert_eq!(number, 10f64);
@@ -349,8 +1313,8 @@ mod expression_tests {
     #[test]
     fn expression_add_two_numbers() {
         let code = "10 + 10;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(number)) => {
                 assert_eq!(number, 20f64);
@@ -362,8 +1326,8 @@ mod expression_tests {
     #[test]
     fn expression_add_two_strings() {
         let code = "\"10\" + \"10\";";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::TextString(string) => {
                 assert_eq!(string, "1010");
@@ -375,8 +1339,8 @@ mod expression_tests {
     #[test]
     fn expression_add_number_to_string() {
         let code = "\"10\" + 10;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::TextString(string) => {
                 assert_eq!(string, "1010");
@@ -388,8 +1352,8 @@ mod expression_tests {
     #[test]
     fn expression_add_string_to_number() {
         let code = "10 + \"10\";";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::TextString(string) => {
                 assert_eq!(string, "1010");
@@ -401,8 +1365,8 @@ mod expression_tests {
     #[test]
     fn expression_add_number_to_undefined() {
         let code = "undefined + 10;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(num) => {
                 assert_eq!(num, Num::NaN);
@@ -414,8 +1378,8 @@ mod expression_tests {
     #[test]
     fn expression_subtract_string_from_string() {
         let code = "\"jake\" - \"e\";";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(num) => {
                 assert_eq!(num, Num::NaN);
@@ -427,8 +1391,8 @@ mod expression_tests {
     #[test]
     fn expression_subtract_number_from_number() {
         let code = "20 - 17;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(num)) => {
                 assert_eq!(num, 3f64);
@@ -440,8 +1404,8 @@ mod expression_tests {
     #[test]
     fn expression_multiple_operators() {
         let code = "20 - 17 + 7;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(num)) => {
                 assert_eq!(num, 10f64);
@@ -453,8 +1417,8 @@ mod expression_tests {
     #[test]
     fn expression_multiply_two_numbers() {
         let code = "3 * 3;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(num)) => {
                 assert_eq!(num, 9f64);
@@ -466,8 +1430,8 @@ mod expression_tests {
     #[test]
     fn expression_multiply_two_numbers_add_one() {
         let code = "3 * 3 + 4;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(num)) => {
                 assert_eq!(num, 13f64);
@@ -479,8 +1443,8 @@ mod expression_tests {
     #[test]
     fn expression_add_number_to_multiplied_numbers() {
         let code = "4 + 3 * 3;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(num)) => {
                 assert_eq!(num, 13f64);
@@ -492,8 +1456,8 @@ mod expression_tests {
     #[test]
     fn expression_lots_of_operators() {
         let code = "10 + 9 + 6 - 8 * 10 / 2 + 9 - 4;";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(num)) => {
                 assert_eq!(num, -10f64);
@@ -505,8 +1469,8 @@ mod expression_tests {
     #[test]
     fn expression_parentheses() {
         let code = "4 * (4 + 2);";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(num)) => {
                 assert_eq!(num, 24f64);
@@ -518,8 +1482,8 @@ mod expression_tests {
     #[test]
     fn expression_lots_of_operators_with_parentheses() {
         let code = "10 + 9 + 6 - 8 * 10 / (2 + 9 - 4);";
-        let mut interpreter = Interpreter::new(code.chars().collect());
-        let result = interpreter.expression();
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(code).unwrap();
         match result {
             Type::Number(Num::F64(num)) => {
                 assert_eq!(num, 13.571428571428571f64);
@@ -536,11 +1500,11 @@ mod assign_tests {
     #[test]
     fn assign_number() {
         let source = "var jake = 26;";
-        let mut interpreter = Interpreter::new(source.chars().collect());
-        interpreter.assign();
-        let value = interpreter.value_table.get("jake");
+        let mut interpreter = Interpreter::new();
+        interpreter.eval(source).unwrap();
+        let value = interpreter.get_global("jake");
         match value.unwrap() {
-            Type::Number(Num::F64(val)) => assert_eq!(*val, 26f64),
+            Type::Number(Num::F64(val)) => assert_eq!(val, 26f64),
             actual => panic!("Expected f64 found {:?}", actual),
         }
     }
@@ -548,11 +1512,11 @@ mod assign_tests {
     #[test]
     fn assign_string() {
         let source = "var jake = \"jake\";";
-        let mut interpreter = Interpreter::new(source.chars().collect());
-        interpreter.assign();
-        let value = interpreter.value_table.get("jake");
+        let mut interpreter = Interpreter::new();
+        interpreter.eval(source).unwrap();
+        let value = interpreter.get_global("jake");
         match value.unwrap() {
-            Type::TextString(val) => assert_eq!(*val, "jake"),
+            Type::TextString(val) => assert_eq!(val, "jake"),
             actual => panic!("Expected string found {:?}", actual),
         }
     }
@@ -560,15 +1524,12 @@ mod assign_tests {
     #[test]
     fn assign_string_no_keyword() {
         let source = "jake = \"jake\";";
-        let mut interpreter = Interpreter::new(source.chars().collect());
-        interpreter.value_table.insert(
-            String::from("jake"),
-            Type::TextString(String::from("carrington")),
-        );
-        interpreter.assign();
-        let value = interpreter.value_table.get("jake");
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("jake", Type::TextString(String::from("carrington")));
+        interpreter.eval(source).unwrap();
+        let value = interpreter.get_global("jake");
         match value.unwrap() {
-            Type::TextString(val) => assert_eq!(*val, "jake"),
+            Type::TextString(val) => assert_eq!(val, "jake"),
             actual => panic!("Expected string found {:?}", actual),
         }
     }
@@ -577,16 +1538,17 @@ mod assign_tests {
 #[cfg(test)]
 mod program_tests {
     use super::*;
+    use crate::error::ErrorKind;
 
     #[test]
     fn program_test() {
         let source = "var a = 10;
         let b = 20;
         const c = a + b;";
-        let mut interpreter = Interpreter::new(source.chars().collect());
-        interpreter.program();
-        match interpreter.value_table.get("c").unwrap() {
-            Type::Number(Num::F64(val)) => assert_eq!(*val, 30f64),
+        let mut interpreter = Interpreter::new();
+        interpreter.eval(source).unwrap();
+        match interpreter.get_global("c").unwrap() {
+            Type::Number(Num::F64(val)) => assert_eq!(val, 30f64),
             actual => panic!("Expected 30 found {:?}", actual),
         }
     }
@@ -598,9 +1560,1424 @@ mod program_tests {
             var b = 20;
             var c = a + b;
         }";
-        let mut interpreter = Interpreter::new(source.chars().collect());
-        interpreter.function();
-        println!("{:?}", interpreter.function_table);
-        // panic!("PRINT");
+        let mut interpreter = Interpreter::new();
+        interpreter.eval(source).unwrap();
+        match interpreter.get_global("myFunction").unwrap() {
+            Type::Function(function) => match &function.body {
+                FunctionBody::Script { body, .. } => assert_eq!(body.len(), 3),
+                actual => panic!("Expected script body found {:?}", actual),
+            },
+            actual => panic!("Expected function found {:?}", actual),
+        }
+    }
+
+    #[test]
+    fn undeclared_variable() {
+        let source = "var a = 10;
+        var b = a + c;";
+        let mut interpreter = Interpreter::new();
+        let error = interpreter.eval(source).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ReferenceError);
+        assert_eq!(error.message, "c is not defined");
+        assert_eq!(
+            error.position,
+            Some(Position {
+                line: 2,
+                column: 21
+            })
+        );
+    }
+
+    #[test]
+    fn call_function() {
+        let source = "var a = 1;
+        function setA() {
+            a = 2;
+        }
+        setA();";
+        let mut interpreter = Interpreter::new();
+        interpreter.eval(source).unwrap();
+        match interpreter.get_global("a").unwrap() {
+            Type::Number(Num::F64(val)) => assert_eq!(val, 2f64),
+            actual => panic!("Expected 2 found {:?}", actual),
+        }
+    }
+
+    #[test]
+    fn call_non_function() {
+        let source = "var a = 1;
+        console.warn(a);";
+        let mut interpreter = Interpreter::new();
+        let error = interpreter.eval(source).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TypeError);
+        assert_eq!(error.message, "console.warn is not a function");
+        assert_eq!(
+            error.position,
+            Some(Position {
+                line: 2,
+                column: 21
+            })
+        );
+    }
+}
+
+#[cfg(test)]
+mod scope_tests {
+    use super::test_support::*;
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn block_functions_are_hoisted_in_sloppy_mode() {
+        assert_eq!(inspect("{ function f() { return 1 } } f()"), "1");
+        assert_eq!(
+            inspect("if (true) { function g() {} } typeof g"),
+            "'function'"
+        );
+        assert_eq!(
+            inspect("typeof g; if (false) { function g() {} } typeof g"),
+            "'undefined'"
+        );
+        assert_eq!(
+            inspect("function o() { { function i() { return 1 } } return i() } o()"),
+            "1"
+        );
+        assert_eq!(inspect("let a = 1; { function a() {} } a"), "1");
+        assert_eq!(
+            inspect("function p(f) { { function f() {} } return f } p(2)"),
+            "2"
+        );
+        assert_eq!(inspect("if (true) function k() { return 3 } k()"), "3");
+        assert_eq!(
+            inspect("var r = typeof f; { r += typeof f; function f() {} } r + typeof f"),
+            "'undefinedfunctionfunction'"
+        );
+        assert_eq!(
+            error("'use strict'; { function s() {} } s").kind,
+            ErrorKind::ReferenceError
+        );
+    }
+
+    #[test]
+    fn let_is_block_scoped() {
+        assert_eq!(number("let a = 1; { let a = 2; } a"), 1f64);
+        assert_eq!(error("{ let b = 1; } b").kind, ErrorKind::ReferenceError);
+        assert_eq!(error("{ const c = 1; } c").kind, ErrorKind::ReferenceError);
+    }
+
+    #[test]
+    fn var_is_function_scoped() {
+        assert_eq!(number("{ var a = 5; } a"), 5f64);
+        assert_eq!(
+            error("function f() { var b = 1; } f(); b").kind,
+            ErrorKind::ReferenceError
+        );
+    }
+
+    #[test]
+    fn var_is_hoisted() {
+        let mut interpreter = Interpreter::new();
+        let value = interpreter
+            .eval(
+                "var seen = 1;
+                function f() {
+                    seen = a;
+                    { var a = 2; }
+                }
+                f();
+                seen",
+            )
+            .unwrap();
+        assert!(matches!(value, Type::Undefined));
+        assert!(interpreter.get_global("a").is_none());
+    }
+
+    #[test]
+    fn functions_are_hoisted() {
+        assert_eq!(number("var a = 1; f(); function f() { a = 2; } a"), 2f64);
+    }
+
+    #[test]
+    fn inner_scopes_see_outer_bindings() {
+        assert_eq!(number("var n = 1; { let m = 2; { n = n + m; } } n"), 3f64);
+        assert_eq!(number("let x = 1; { let x = 10; x = x + 1; } x"), 1f64);
+    }
+}
+
+#[cfg(test)]
+mod binding_tests {
+    use super::test_support::*;
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn const_cannot_be_reassigned() {
+        let error = error("const a = 1;\na = 2;");
+        assert_eq!(error.kind, ErrorKind::TypeError);
+        assert_eq!(error.message, "Assignment to constant variable.");
+        assert_eq!(error.position, Some(Position { line: 2, column: 1 }));
+    }
+
+    #[test]
+    fn const_can_be_shadowed() {
+        let mut interpreter = Interpreter::new();
+        let value = interpreter
+            .eval("const a = 1; { let a = 2; a = 3; } a")
+            .unwrap();
+        assert!(matches!(value, Type::Number(Num::F64(n)) if n == 1f64));
+    }
+
+    #[test]
+    fn temporal_dead_zone() {
+        for source in ["a; let a = 1;", "{ a = 2; let a; }", "const a = a;"] {
+            let error = error(source);
+            assert_eq!(error.kind, ErrorKind::ReferenceError, "{}", source);
+            assert_eq!(error.message, "Cannot access 'a' before initialization");
+        }
+    }
+
+    #[test]
+    fn dead_zone_shadows_outer_binding() {
+        let error = error("let a = 1; { a; let a = 2; }");
+        assert_eq!(error.kind, ErrorKind::ReferenceError);
+    }
+
+    #[test]
+    fn let_without_initializer_is_undefined() {
+        let value = Interpreter::new().eval("let a; a").unwrap();
+        assert!(matches!(value, Type::Undefined));
+    }
+
+    #[test]
+    fn redeclaration_is_a_syntax_error() {
+        let mut interpreter = Interpreter::new();
+        let error = interpreter.eval("var ran = 1; let b; let b;").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::SyntaxError);
+        assert!(interpreter.get_global("ran").is_none());
+    }
+}
+
+#[cfg(test)]
+mod call_tests {
+    use super::test_support::*;
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn params_are_bound_to_arguments() {
+        assert_eq!(
+            number("function add(a, b) { return a + b; } add(2, 3)"),
+            5f64
+        );
+    }
+
+    #[test]
+    fn calls_inside_expressions() {
+        assert_eq!(
+            number(
+                "function double(n) { return n * 2; }
+                function add(a, b) { return a + b; }
+                add(double(2), 1) * double(double(1)) + 1"
+            ),
+            21f64
+        );
+    }
+
+    #[test]
+    fn missing_arguments_are_undefined() {
+        assert!(matches!(
+            eval("function second(a, b) { return b; } second(1)"),
+            Type::Undefined
+        ));
+    }
+
+    #[test]
+    fn return_stops_the_function() {
+        assert_eq!(
+            number(
+                "var after = 0;
+                function f() { { return 1; } after = 1; }
+                f() + after"
+            ),
+            1f64
+        );
+        assert!(matches!(
+            eval("function f() { return; } f()"),
+            Type::Undefined
+        ));
+        assert!(matches!(eval("function f() { 1; } f()"), Type::Undefined));
+    }
+
+    #[test]
+    fn return_on_its_own_line_returns_undefined() {
+        assert!(matches!(
+            eval("function f() { return\n1; } f()"),
+            Type::Undefined
+        ));
+    }
+
+    #[test]
+    fn functions_call_other_functions() {
+        assert_eq!(
+            number(
+                "function f(n) { return g(n) + 1; }
+                function g(n) { return n * 2; }
+                f(3)"
+            ),
+            7f64
+        );
+    }
+
+    #[test]
+    fn arguments_object() {
+        assert_eq!(
+            number("function count() { return arguments.length; } count(1, 2, 3)"),
+            3f64
+        );
+        assert_eq!(
+            number("function third() { return arguments[2]; } third(1, 2, 3)"),
+            3f64
+        );
+        assert_eq!(
+            number("function f(a) { return arguments[0] + a; } f(4)"),
+            8f64
+        );
+    }
+
+    #[test]
+    fn params_are_local() {
+        let mut interpreter = Interpreter::new();
+        let error = interpreter
+            .eval("function f(a) { var b = a; } f(1); a")
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ReferenceError);
+    }
+
+    #[test]
+    fn return_outside_function() {
+        let error = Interpreter::new().eval("return 1;").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::SyntaxError);
+        assert_eq!(error.message, "Illegal return statement");
+    }
+}
+
+#[cfg(test)]
+mod closure_tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn closures_share_their_body() {
+        let mut interpreter = Interpreter::new();
+        let functions = interpreter
+            .eval("var fs = []; for (let i = 0; i < 2; i++) { fs[i] = () => i; } fs")
+            .unwrap();
+        let body = |index: usize| match &functions {
+            Type::Array(array) => match &array.borrow()[index] {
+                Type::Function(function) => match &function.body {
+                    FunctionBody::Script { body, .. } => Rc::clone(body),
+                    FunctionBody::Native(_) => panic!("Expected a script function"),
+                },
+                actual => panic!("Expected function found {:?}", actual),
+            },
+            actual => panic!("Expected array found {:?}", actual),
+        };
+        assert!(Rc::ptr_eq(&body(0), &body(1)));
+    }
+
+    #[test]
+    fn counter() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "function makeCounter() {
+                    let count = 0;
+                    return function () {
+                        count = count + 1;
+                        return count;
+                    };
+                }
+                var counter = makeCounter();
+                var other = makeCounter();",
+            )
+            .unwrap();
+        assert_eq!(number_in(&mut interpreter, "counter()"), 1f64);
+        assert_eq!(number_in(&mut interpreter, "counter()"), 2f64);
+        assert_eq!(number_in(&mut interpreter, "other()"), 1f64);
+    }
+
+    #[test]
+    fn factory_captures_params() {
+        let mut interpreter = Interpreter::new();
+        let source = "function adder(a) { function add(b) { return a + b; } return add; }
+            var addTen = adder(10);
+            addTen(5) + adder(1)(1)";
+        assert_eq!(number_in(&mut interpreter, source), 17f64);
+    }
+
+    #[test]
+    fn captured_variables_are_shared() {
+        let mut interpreter = Interpreter::new();
+        let source = "var get, set;
+            function make() {
+                var value = 1;
+                get = function () { return value; };
+                set = function (v) { value = v; };
+            }
+            make();
+            set(42);
+            get()";
+        assert_eq!(number_in(&mut interpreter, source), 42f64);
+    }
+
+    #[test]
+    fn callbacks_see_their_defining_scope() {
+        let mut interpreter = Interpreter::new();
+        let source = "function call(callback) { var x = 'inner'; return callback(); }
+            function outer() { var x = 2; return call(function () { return x * 10; }); }
+            outer()";
+        assert_eq!(number_in(&mut interpreter, source), 20f64);
+    }
+
+    #[test]
+    fn functions_see_later_changes_to_globals() {
+        let mut interpreter = Interpreter::new();
+        let source = "var a = 1; function f() { return a; } a = 5; f()";
+        assert_eq!(number_in(&mut interpreter, source), 5f64);
+    }
+
+    #[test]
+    fn named_function_expressions_see_their_own_name() {
+        let mut interpreter = Interpreter::new();
+        let source =
+            "var f = function g(n) { return n; }; var h = function g2() { return g2; }; f(3)";
+        assert_eq!(number_in(&mut interpreter, source), 3f64);
+        assert!(interpreter.eval("g").is_err());
+        assert!(matches!(
+            interpreter.eval("h()").unwrap(),
+            Type::Function(_)
+        ));
+    }
+}
+
+#[cfg(test)]
+mod arrow_tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn concise_bodies() {
+        let mut interpreter = Interpreter::new();
+        let source = "var double = x => x * 2;
+            var add = (a, b) => a + b;
+            var one = () => 1;
+            add(double(3), one())";
+        assert_eq!(number_in(&mut interpreter, source), 7f64);
+    }
+
+    #[test]
+    fn block_bodies() {
+        let mut interpreter = Interpreter::new();
+        let source = "var f = (a) => { let b = a + 1; return b * 2; }; f(1)";
+        assert_eq!(number_in(&mut interpreter, source), 4f64);
+        assert!(matches!(
+            interpreter.eval("(() => { 1; })()").unwrap(),
+            Type::Undefined
+        ));
+    }
+
+    #[test]
+    fn arrows_as_arguments() {
+        let mut interpreter = Interpreter::new();
+        let source = "function apply(f, value) { return f(value); }
+            apply(n => n * 10, 4) + apply((n) => { return n; }, 2)";
+        assert_eq!(number_in(&mut interpreter, source), 42f64);
+    }
+
+    #[test]
+    fn arrows_close_over_their_scope() {
+        let mut interpreter = Interpreter::new();
+        let source = "var adder = a => b => a + b; adder(2)(3)";
+        assert_eq!(number_in(&mut interpreter, source), 5f64);
+    }
+
+    #[test]
+    fn lexical_arguments() {
+        let mut interpreter = Interpreter::new();
+        let source = "function f() { var g = () => arguments[0]; return g(5); } f(1)";
+        assert_eq!(number_in(&mut interpreter, source), 1f64);
+    }
+
+    #[test]
+    fn lexical_this() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "function method() { var arrow = () => this; return arrow(); }
+                function plain() { return this; }",
+            )
+            .unwrap();
+        let mut object = Properties::new();
+        object.insert(
+            String::from("method"),
+            interpreter.get_global("method").unwrap(),
+        );
+        let object = Rc::new(RefCell::new(object));
+        interpreter.set_global("object", Type::Object(object.clone()));
+        match interpreter.eval("object.method()").unwrap() {
+            Type::Object(this) => assert!(Rc::ptr_eq(&this, &object)),
+            actual => panic!("Expected object found {:?}", actual),
+        }
+        assert!(matches!(
+            interpreter.eval("plain()").unwrap(),
+            Type::Undefined
+        ));
+        assert!(matches!(
+            interpreter.eval("(() => this)()").unwrap(),
+            Type::Undefined
+        ));
+    }
+}
+
+#[cfg(test)]
+mod conditional_tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn if_else_chains() {
+        let source = "function grade(n) {
+                if (n) {
+                    if (n - 1) return 'many';
+                    else return 'one';
+                } else if (n ?? 'missing') {
+                    return n ?? 'missing';
+                }
+                return 'none';
+            }";
+        let mut interpreter = Interpreter::new();
+        interpreter.eval(source).unwrap();
+        assert_eq!(interpreter.eval("grade(2)").unwrap().inspect(), "'many'");
+        assert_eq!(interpreter.eval("grade(1)").unwrap().inspect(), "'one'");
+        assert_eq!(interpreter.eval("grade(0)").unwrap().inspect(), "'none'");
+        assert_eq!(interpreter.eval("grade()").unwrap().inspect(), "'missing'");
+    }
+
+    #[test]
+    fn if_without_else() {
+        assert_eq!(inspect("var a = 1; if ('') a = 2; a"), "1");
+        assert_eq!(inspect("var a = 1; if ('x') { a = 2; } a"), "2");
+    }
+
+    #[test]
+    fn var_in_if_is_hoisted() {
+        assert_eq!(inspect("if (0) { var a = 1; } a"), "undefined");
+    }
+
+    #[test]
+    fn ternary() {
+        assert_eq!(inspect("1 ? 'yes' : 'no'"), "'yes'");
+        assert_eq!(inspect("null ? 'yes' : 'no'"), "'no'");
+        assert_eq!(inspect("0 ? 1 : '' ? 2 : 3"), "3");
+    }
+
+    #[test]
+    fn computed_nan_is_falsy() {
+        assert_eq!(inspect("var a = 1; if (0 / 0) { a = 2; } a"), "1");
+        assert_eq!(inspect("!!(Infinity - Infinity)"), "false");
+        assert_eq!(inspect("(0 / 0) ? 'a' : 'b'"), "'b'");
+        assert_eq!(inspect("('x' * 2) || 'fallback'"), "'fallback'");
+        assert_eq!(inspect("var n = 0; while (n / n) { n++; } n"), "0");
+    }
+
+    #[test]
+    fn logical_operators_return_operands() {
+        assert_eq!(inspect("'a' && 'b'"), "'b'");
+        assert_eq!(inspect("0 && 'b'"), "0");
+        assert_eq!(inspect("'' || 'b'"), "'b'");
+        assert_eq!(inspect("'a' || 'b'"), "'a'");
+        assert_eq!(inspect("null ?? 'b'"), "'b'");
+        assert_eq!(inspect("0 ?? 'b'"), "0");
+        assert_eq!(inspect("1 && 0 || 'c'"), "'c'");
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "var calls = 0;
+                function touch() { calls = calls + 1; return true; }
+                0 && touch();
+                1 || touch();
+                1 ?? touch();
+                1 ? 1 : touch();
+                1 && touch();",
+            )
+            .unwrap();
+        assert_eq!(interpreter.eval("calls").unwrap().inspect(), "1");
+    }
+
+    #[test]
+    fn missing_operand_is_not_evaluated() {
+        assert_eq!(inspect("true || missing"), "true");
+        assert_eq!(inspect("false && missing.property"), "false");
+    }
+}
+
+#[cfg(test)]
+mod loop_tests {
+    use super::test_support::*;
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn while_loop() {
+        assert_eq!(
+            inspect("var n = 4, total = 0; while (n) { total = total + n; n = n - 1; } total"),
+            "10"
+        );
+        assert_eq!(inspect("var ran = 0; while (0) ran = 1; ran"), "0");
+    }
+
+    #[test]
+    fn do_while_runs_at_least_once() {
+        assert_eq!(
+            inspect("var ran = 0; do ran = ran + 1; while (0); ran"),
+            "1"
+        );
+        assert_eq!(
+            inspect("var n = 3, s = ''; do { s = s + n; n = n - 1; } while (n) s"),
+            "'321'"
+        );
+    }
+
+    #[test]
+    fn for_loop() {
+        assert_eq!(
+            inspect("var s = ''; for (var i = 3; i; i = i - 1) s = s + i; s + i"),
+            "'3210'"
+        );
+        assert_eq!(
+            inspect("var n = 2; for (;;) { n = n - 1; if (n) continue; break; } n"),
+            "0"
+        );
+    }
+
+    #[test]
+    fn for_let_is_scoped_to_the_loop() {
+        let error = Interpreter::new()
+            .eval("for (let i = 1; i; i = i - 1) {} i")
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ReferenceError);
+    }
+
+    #[test]
+    fn for_let_binds_per_iteration() {
+        let source = "var first, second;
+            for (let i = 2; i; i = i - 1) {
+                if (first) second = () => i;
+                else first = () => i;
+            }
+            first() + second() * 10";
+        assert_eq!(inspect(source), "12");
+        assert_eq!(inspect(&source.replace("let i", "var i")), "0");
+    }
+
+    #[test]
+    fn break_and_continue() {
+        let source = "var s = '';
+            for (var i = 5; i; i = i - 1) {
+                if (i - 4) {} else continue;
+                if (i - 2) {} else break;
+                s = s + i;
+            }
+            s";
+        assert_eq!(inspect(source), "'53'");
+    }
+
+    #[test]
+    fn labelled_break_and_continue() {
+        let source = "var s = '';
+            outer: for (var i = 3; i; i = i - 1) {
+                for (var j = 3; j; j = j - 1) {
+                    if (j - 2) {} else continue outer;
+                    if (i - 1) {} else break outer;
+                    s = s + i + j;
+                }
+            }
+            s";
+        assert_eq!(inspect(source), "'3323'");
+        assert_eq!(
+            inspect("var a = 1; block: { a = 2; break block; a = 3; } a"),
+            "2"
+        );
+    }
+
+    #[test]
+    fn return_inside_loop() {
+        let source = "function f() { while (1) { return 'done'; } } f()";
+        assert_eq!(inspect(source), "'done'");
+    }
+
+    #[test]
+    fn jump_errors() {
+        for (source, message) in [
+            ("break;", "Illegal break statement"),
+            (
+                "continue;",
+                "Illegal continue statement: no surrounding iteration statement",
+            ),
+            ("while (0) { break nowhere; }", "Undefined label 'nowhere'"),
+            (
+                "a: { while (0) continue a; }",
+                "Illegal continue statement: 'a' does not denote an iteration statement",
+            ),
+            (
+                "while (1) { function f() { break; } }",
+                "Illegal break statement",
+            ),
+        ] {
+            let error = error(source);
+            assert_eq!(error.kind, ErrorKind::SyntaxError, "{}", source);
+            assert_eq!(error.message, message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod comparison_tests {
+    use super::test_support::*;
+
+    #[test]
+    fn relational_operators() {
+        assert_eq!(inspect("1 < 2"), "true");
+        assert_eq!(inspect("2 > 10"), "false");
+        assert_eq!(inspect("'2' > '10'"), "true");
+        assert_eq!(inspect("'2' > 10"), "false");
+        assert_eq!(inspect("2 <= 2"), "true");
+        assert_eq!(inspect("null >= 0"), "true");
+        assert_eq!(inspect("undefined <= 0"), "false");
+        assert_eq!(inspect("undefined >= 0"), "false");
+    }
+
+    #[test]
+    fn equality_operators() {
+        assert_eq!(inspect("null == undefined"), "true");
+        assert_eq!(inspect("null === undefined"), "false");
+        assert_eq!(inspect("'1' == 1"), "true");
+        assert_eq!(inspect("'1' != 1"), "false");
+        assert_eq!(inspect("'1' !== 1"), "true");
+        assert_eq!(inspect("undefined + 1 == undefined + 1"), "false");
+        assert_eq!(inspect("function f() {} f === f"), "true");
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(inspect("1 + 1 < 3 == 2 > 1"), "true");
+        assert_eq!(inspect("1 < 2 && 2 < 3 ? 'yes' : 'no'"), "'yes'");
+    }
+
+    #[test]
+    fn counting_loop() {
+        assert_eq!(
+            inspect("var s = ''; for (let i = 0; i < 5; i = i + 1) { if (i == 3) continue; s = s + i; } s"),
+            "'0124'"
+        );
+    }
+}
+
+#[cfg(test)]
+mod operator_tests {
+    use super::test_support::*;
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn arithmetic_precedence() {
+        assert_eq!(inspect("2 * 3 * 4"), "24");
+        assert_eq!(inspect("1 + 2 * 3 - 4 / 2"), "5");
+        assert_eq!(inspect("-2 * 3"), "-6");
+        assert_eq!(inspect("- -2"), "2");
+        assert_eq!(inspect("+'3' + 1"), "4");
+    }
+
+    #[test]
+    fn exponentiation() {
+        assert_eq!(inspect("2 ** 3 ** 2"), "512");
+        assert_eq!(inspect("(2 ** 3) ** 2"), "64");
+        assert_eq!(inspect("(-2) ** 2"), "4");
+        assert_eq!(inspect("2 * 3 ** 2"), "18");
+        assert_eq!(inspect("1 ** (1 / 0)"), "NaN");
+    }
+
+    #[test]
+    fn comma_operator() {
+        assert_eq!(inspect("(1, 2)"), "2");
+        assert_eq!(inspect("var a, b; a = 1, b = a + 1; b"), "2");
+    }
+
+    #[test]
+    fn in_operator() {
+        assert_eq!(
+            inspect("function f() { return 0 in arguments } f(1)"),
+            "true"
+        );
+        assert_eq!(
+            inspect("function f() { return 1 in arguments } f(1)"),
+            "false"
+        );
+        assert_eq!(
+            Interpreter::new().eval("'a' in 1").unwrap_err().message,
+            "Cannot use 'in' operator to search for 'a' in 1"
+        );
+    }
+
+    #[test]
+    fn instance_of() {
+        assert_eq!(inspect("function F() {} ({}) instanceof F"), "false");
+        assert_eq!(
+            inspect("function F() {} [] instanceof F || F instanceof F"),
+            "false"
+        );
+        assert_eq!(inspect("1 instanceof (() => 1)"), "false");
+        for (source, message) in [
+            (
+                "1 instanceof 1",
+                "Right-hand side of 'instanceof' is not an object",
+            ),
+            (
+                "1 instanceof {}",
+                "Right-hand side of 'instanceof' is not callable",
+            ),
+            (
+                "({}) instanceof (() => 1)",
+                "Function has non-object prototype 'undefined' in instanceof check",
+            ),
+            (
+                "[] instanceof console.log",
+                "Function has non-object prototype 'undefined' in instanceof check",
+            ),
+        ] {
+            let error = error(source);
+            assert_eq!(error.kind, ErrorKind::TypeError, "{}", source);
+            assert_eq!(error.message, message, "{}", source);
+        }
+    }
+}
+
+#[cfg(test)]
+mod unary_tests {
+    use super::test_support::*;
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn typeof_operator() {
+        for (source, expected) in [
+            ("typeof 1", "'number'"),
+            ("typeof (0 / 0)", "'number'"),
+            ("typeof 'a'", "'string'"),
+            ("typeof true", "'boolean'"),
+            ("typeof undefined", "'undefined'"),
+            ("typeof null", "'object'"),
+            ("typeof console", "'object'"),
+            ("typeof function () {}", "'function'"),
+            ("typeof (() => 1)", "'function'"),
+            ("typeof typeof 1", "'string'"),
+            ("typeof 1 + 2", "'number2'"),
+        ] {
+            assert_eq!(inspect(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn typeof_undeclared_name() {
+        assert_eq!(inspect("typeof missing"), "'undefined'");
+        assert_eq!(inspect("typeof missing === 'undefined'"), "true");
+        let error = Interpreter::new().eval("typeof x; let x = 1").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ReferenceError);
+        let error = Interpreter::new().eval("typeof missing.a").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ReferenceError);
+    }
+
+    #[test]
+    fn arithmetic_and_logical_operators() {
+        assert_eq!(inspect("-5"), "-5");
+        assert_eq!(inspect("-'5'"), "-5");
+        assert_eq!(inspect("+true"), "1");
+        assert_eq!(inspect("+'abc'"), "NaN");
+        assert_eq!(inspect("!0"), "true");
+        assert_eq!(inspect("!!'a'"), "true");
+        assert_eq!(inspect("var x = 1; !x"), "false");
+        assert_eq!(inspect("~5"), "-6");
+        assert_eq!(inspect("~-1"), "0");
+        assert_eq!(inspect("~~3.7"), "3");
+        assert_eq!(inspect("~undefined"), "-1");
+    }
+
+    #[test]
+    fn void_operator() {
+        assert_eq!(inspect("void 0"), "undefined");
+        assert_eq!(inspect("var a = 1; void (a = 2); a"), "2");
+    }
+
+    #[test]
+    fn delete_operator() {
+        let source = "function f() { return arguments } var a = f(1, 2);";
+        assert_eq!(inspect(&format!("{} delete a[0]", source)), "true");
+        assert_eq!(
+            inspect(&format!("{} delete a[0]; a", source)),
+            "{ '1': 2, length: 2 }"
+        );
+        assert_eq!(
+            inspect(&format!("{} delete a.length; 'length' in a", source)),
+            "false"
+        );
+        assert_eq!(inspect(&format!("{} delete a.missing", source)), "true");
+        assert_eq!(inspect("var b = 1; delete b"), "false");
+        assert_eq!(inspect("delete 1"), "true");
+        let error = Interpreter::new().eval("delete undefined.a").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TypeError);
+    }
+}
+
+#[cfg(test)]
+mod assignment_tests {
+    use super::test_support::*;
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn assignment_is_an_expression() {
+        assert_eq!(inspect("var a, b; a = b = 3; a + b"), "6");
+        assert_eq!(inspect("var a; (a = 2) * 5"), "10");
+        assert_eq!(inspect("var a; if (a = 1) a = 'yes'; a"), "'yes'");
+    }
+
+    #[test]
+    fn compound_assignment() {
+        for (source, expected) in [
+            ("var a = 1; a += 2", "3"),
+            ("var a = 'a'; a += 1; a", "'a1'"),
+            ("var a = 5; a -= 2", "3"),
+            ("var a = 5; a *= 2", "10"),
+            ("var a = 5; a /= 2", "2.5"),
+            ("var a = 3; a **= 2", "9"),
+            ("var a = 1; var b = 2; a += b += 3; a", "6"),
+        ] {
+            assert_eq!(inspect(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn logical_assignment() {
+        assert_eq!(inspect("var a = 0; a ||= 5; a"), "5");
+        assert_eq!(inspect("var a = 1; a ||= 5; a"), "1");
+        assert_eq!(inspect("var a = 1; a &&= 5; a"), "5");
+        assert_eq!(inspect("var a = null; a ??= 'x'; a"), "'x'");
+        assert_eq!(inspect("var a = 0; a ??= 'x'; a"), "0");
+        // The right side isn't evaluated when the assignment short-circuits.
+        assert_eq!(inspect("var n = 0; var a = 1; a ||= n = 1; n"), "0");
+        // A short-circuited assignment never writes, so it doesn't trip over const.
+        assert_eq!(inspect("const c = 1; c ||= 2"), "1");
+    }
+
+    #[test]
+    fn increment_and_decrement() {
+        assert_eq!(inspect("var a = 1; a++"), "1");
+        assert_eq!(inspect("var a = 1; a++; a"), "2");
+        assert_eq!(inspect("var a = 1; ++a"), "2");
+        assert_eq!(inspect("var a = 1; a--; a"), "0");
+        assert_eq!(inspect("var a = 1; --a"), "0");
+        assert_eq!(inspect("var a = '5'; a++; a"), "6");
+        assert_eq!(inspect("var a = '5'; a++"), "5");
+        assert_eq!(inspect("var a; a++; a"), "NaN");
+        assert_eq!(
+            inspect("var s = 0; for (var i = 0; i < 4; i++) s += i; s"),
+            "6"
+        );
+    }
+
+    #[test]
+    fn member_targets() {
+        let source = "function f() { return arguments } var o = f(1, 2);";
+        for (tail, expected) in [
+            ("o.x = 5; o.x", "5"),
+            ("o[0] += 10; o[0]", "11"),
+            ("o['1']++; o[1]", "3"),
+            ("++o.length", "3"),
+            ("o.y ??= 'set'; o.y", "'set'"),
+            ("var k = 0; o[k++] = 'a'; o[0] + k", "'a1'"),
+        ] {
+            assert_eq!(
+                inspect(&format!("{} {}", source, tail)),
+                expected,
+                "{}",
+                tail
+            );
+        }
+        assert_eq!(inspect("var n = 1; n.x = 2; n.x"), "undefined");
+    }
+
+    #[test]
+    fn assignment_errors() {
+        let error = Interpreter::new().eval("const c = 1; c += 1").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TypeError);
+        let error = Interpreter::new().eval("const c = 1; c++").unwrap_err();
+        assert_eq!(error.message, "Assignment to constant variable.");
+        let error = Interpreter::new().eval("missing += 1").unwrap_err();
+        assert_eq!(error.message, "missing is not defined");
+        let error = Interpreter::new().eval("var u; u.x = 1").unwrap_err();
+        assert_eq!(
+            error.message,
+            "Cannot set properties of undefined (setting 'x')"
+        );
+    }
+}
+
+#[cfg(test)]
+mod bitwise_tests {
+    use super::test_support::*;
+
+    #[test]
+    fn remainder() {
+        for (source, expected) in [
+            ("7 % 3", "1"),
+            ("-7 % 3", "-1"),
+            ("7 % -3", "1"),
+            ("5.5 % 2", "1.5"),
+            ("'10' % 4", "2"),
+            ("1 % 0", "NaN"),
+            ("2 + 7 % 3 * 2", "4"),
+        ] {
+            assert_eq!(inspect(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn bitwise_operators() {
+        for (source, expected) in [
+            ("5 & 3", "1"),
+            ("5 | 3", "7"),
+            ("5 ^ 3", "6"),
+            ("-1 & 255", "255"),
+            ("4294967295 | 0", "-1"),
+            ("2147483648 | 0", "-2147483648"),
+            ("1.9 | 0", "1"),
+            ("-1.9 | 0", "-1"),
+            ("(0 / 0) | 0", "0"),
+            ("'12' & '10'", "8"),
+            ("1 | 2 ^ 3 & 4", "3"),
+        ] {
+            assert_eq!(inspect(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn shift_operators() {
+        for (source, expected) in [
+            ("1 << 4", "16"),
+            ("1 << 31", "-2147483648"),
+            ("1 << 32", "1"),
+            ("1 << 33", "2"),
+            ("-16 >> 2", "-4"),
+            ("-16 >>> 2", "1073741820"),
+            ("-1 >>> 0", "4294967295"),
+            ("-1 >>> 31", "1"),
+            ("8 >> -1", "0"),
+            ("1 + 1 << 2", "8"),
+        ] {
+            assert_eq!(inspect(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn compound_assignment() {
+        assert_eq!(inspect("var f = 0; f |= 4; f |= 1; f &= ~4; f"), "1");
+        assert_eq!(inspect("var n = 10; n %= 4; n <<= 3; n >>= 1; n"), "8");
+        assert_eq!(inspect("var n = -1; n >>>= 28; n ^= 5"), "10");
+    }
+
+    #[test]
+    fn string_hash() {
+        // The djb2 hash of the alphabet, which relies on `| 0` wrapping to 32 bits.
+        let source = "
+            var hash = 5381;
+            for (var i = 0; i < 26; i++) {
+                hash = (hash * 33 + (i + 97)) | 0;
+            }
+            hash >>> 0
+        ";
+        assert_eq!(inspect(source), "3927029252");
+    }
+}
+
+#[cfg(test)]
+mod template_tests {
+    use super::test_support::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn interpolation() {
+        assert_eq!(inspect("`plain`"), "'plain'");
+        assert_eq!(
+            inspect("var a = 10; `a ${a} - ${typeof a}`"),
+            "'a 10 - number'"
+        );
+        assert_eq!(
+            inspect("`${1 + 1}${'x'}${null} ${undefined}`"),
+            "'2xnull undefined'"
+        );
+        assert_eq!(inspect("var n = 2; `${`${n}${`${n * 2}`}`}!`"), "'24!'");
+        assert_eq!(inspect("`${'}'}`"), "'}'");
+        assert_eq!(inspect("`${(() => { return '{' })()}`"), "'{'");
+    }
+
+    #[test]
+    fn multi_line_and_escapes() {
+        assert_eq!(inspect("`a\nb`"), "'a\\nb'");
+        assert_eq!(inspect("`a\r\nb`"), "'a\\nb'");
+        assert_eq!(inspect("`\\x41\\u{42}\\\nC \\` $ \\${}`"), "'ABC ` $ ${}'");
+    }
+
+    #[test]
+    fn substitutions_run_in_order() {
+        let source = "var log = ''; function f(x) { log += x; return x } `${f(1)}${f(2)}`; log";
+        assert_eq!(inspect(source), "'12'");
+    }
+
+    #[test]
+    fn string_raw() {
+        assert_eq!(inspect("String.raw`a\\nb`"), "'a\\\\nb'");
+        assert_eq!(inspect("String.raw`${1}\\u{41}${2}`"), "'1\\\\u{41}2'");
+        assert_eq!(inspect("String.raw`\\unicode`"), "'\\\\unicode'");
+        assert_eq!(inspect("String.raw`a\r\nb`"), "'a\\nb'");
+        assert_eq!(inspect("String.raw({ raw: ['a', 'b'] }, 1)"), "'a1b'");
+        assert_eq!(inspect("String.raw({ raw: ['a', 'b', 'c'] }, 1)"), "'a1bc'");
+        assert_eq!(
+            inspect("String.raw({ raw: { 0: 'a', 1: 'b', length: 2 } }, 1, 2)"),
+            "'a1b'"
+        );
+        assert_eq!(error("String.raw({})").kind, ErrorKind::TypeError);
+        assert_eq!(inspect("String.raw({ raw: 'abc' }, 1, 2)"), "'a1b2c'");
+        assert_eq!(inspect("String.raw({ raw: '' }, 1)"), "''");
+        assert_eq!(
+            inspect("String.raw({ raw: { 0: 'a', length: 1.5 } }, 1)"),
+            "'a'"
+        );
+        assert_eq!(
+            error("String.raw({ raw: { length: 1e12 } })").kind,
+            ErrorKind::RangeError
+        );
+    }
+
+    #[test]
+    fn tagged_templates() {
+        let source = "function tag(strings) { return strings } tag`x${1}\\ny${2}`";
+        assert_eq!(
+            inspect(source),
+            "{ '0': 'x', '1': '\\ny', '2': '', length: 3, \
+            raw: { '0': 'x', '1': '\\\\ny', '2': '', length: 3 } }"
+        );
+        assert_eq!(
+            inspect("function t(s, a, b) { return a + b } t`${1}${2}`"),
+            "3"
+        );
+        assert_eq!(
+            inspect("function t(s) { return s[0] } t`\\unicode`"),
+            "undefined"
+        );
+        assert_eq!(
+            inspect("function t() { return arguments.length } t`a${1}b${2}c`"),
+            "3"
+        );
+    }
+
+    #[test]
+    fn tag_receives_this() {
+        let source = "
+            function f() { return this.name }
+            function holder() { return arguments }
+            var o = holder();
+            o.name = 'o';
+            o.tag = f;
+            o.tag`x`
+        ";
+        assert_eq!(inspect(source), "'o'");
+    }
+}
+
+#[cfg(test)]
+mod coercion_tests {
+    use super::test_support::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn addition_coerces_operands() {
+        for (source, expected) in [
+            ("true + true", "2"),
+            ("true + false", "1"),
+            ("null + 10", "10"),
+            ("10 + null", "10"),
+            ("undefined + 10", "NaN"),
+            ("10 + NaN", "NaN"),
+            ("[] + 10", "'10'"),
+            ("10 + []", "'10'"),
+            ("[10, 20] + 10", "'10,2010'"),
+            ("[1] + [2]", "'12'"),
+            ("'jake' + 10", "'jake10'"),
+            ("1 + 2 + '3'", "'33'"),
+            ("'1' + 2 + 3", "'123'"),
+            ("1e21 + ''", "'1e+21'"),
+            ("(function f() {}) + ''", "'function f() {}'"),
+            ("'' + (x => x * 2)", "'x => x * 2'"),
+            ("({ m(a) { return a } }).m + ''", "'m(a) { return a }'"),
+            ("console.log + ''", "'function log() { [native code] }'"),
+        ] {
+            assert_eq!(inspect(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn other_operators_convert_to_numbers() {
+        for (source, expected) in [
+            ("'jake' - 'e'", "NaN"),
+            ("'10' - '4'", "6"),
+            ("'3' * '4'", "12"),
+            ("[6] / ' 2 '", "3"),
+            ("true * 5", "5"),
+            ("null - 1", "-1"),
+            ("undefined * 2", "NaN"),
+            ("[] * 2", "0"),
+            ("[1, 2] * 2", "NaN"),
+            ("'0x10' - 0", "16"),
+        ] {
+            assert_eq!(inspect(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn objects_convert_with_value_of_and_to_string() {
+        let both = "var o = { valueOf() { return 1 }, toString() { return 's' } }; ";
+        for (source, expected) in [
+            ("({ valueOf() { return 5 } }) * 2", "10"),
+            ("({ toString() { return '5' } }) + 1", "'51'"),
+            (&format!("{}o + 1", both), "2"),
+            (&format!("{}`${{o}}`", both), "'s'"),
+            (&format!("{}var p = {{ [o]: 1 }}; p.s + p[o]", both), "2"),
+            (
+                "({ valueOf() { return {} }, toString() { return 't' } }) + 1",
+                "'t1'",
+            ),
+            ("({ valueOf() { return 2 } }) == 2", "true"),
+            ("({ toString() { return 'a' } }) == 'a'", "true"),
+            ("({ valueOf() { return 1 } }) == true", "true"),
+            ("({ valueOf() { return 3 } }) < 4", "true"),
+            ("-({ valueOf() { return 3 } })", "-3"),
+            ("var n = { valueOf() { return 9 } }; n++; n", "10"),
+            (
+                "var a = [1, 2]; a.length = { valueOf() { return 1 } }; a",
+                "[ 1 ]",
+            ),
+            ("({ valueOf: 1 }) + 1", "'[object Object]1'"),
+        ] {
+            assert_eq!(inspect(source), expected, "{}", source);
+        }
+        assert_eq!(
+            inspect(
+                "var log = ''; \
+                ({ valueOf() { log += 'a'; return 1 } }) + \
+                ({ valueOf() { log += 'b'; return 2 } }); log"
+            ),
+            "'ab'"
+        );
+        for source in [
+            "({ toString: 1 }) + ''",
+            "({ valueOf() { return {} }, toString() { return {} } }) * 1",
+        ] {
+            let thrown = error(source);
+            assert_eq!(thrown.kind, ErrorKind::TypeError, "{}", source);
+            assert_eq!(thrown.message, "Cannot convert object to primitive value");
+        }
+    }
+
+    #[test]
+    fn global_number_values() {
+        assert_eq!(inspect("NaN"), "NaN");
+        assert_eq!(inspect("typeof NaN"), "'number'");
+        assert_eq!(inspect("-Infinity"), "-Infinity");
+        assert_eq!(inspect("1 / Infinity"), "0");
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(inspect("[10, 20, 30]"), "[ 10, 20, 30 ]");
+        assert_eq!(inspect("[1, 'a', [null],]"), "[ 1, 'a', [ null ] ]");
+        assert_eq!(inspect("typeof []"), "'object'");
+        assert_eq!(inspect("var a = [1, 2]; a.length + a[1]"), "4");
+        assert_eq!(inspect("var a = [1, 2]; a['0'] + a[2]"), "NaN");
+        assert_eq!(
+            inspect("var a = []; a[2] = 'x'; a"),
+            "[ undefined, undefined, 'x' ]"
+        );
+        assert_eq!(inspect("var a = [1, 2, 3]; a.length = 1; a"), "[ 1 ]");
+        assert_eq!(inspect("var a = [1]; a[0] += 5; a[0]++; a"), "[ 7 ]");
+        assert_eq!(inspect("var a = [1, 2]; a.length = 2.0; a"), "[ 1, 2 ]");
+        assert_eq!(inspect("0 in [1] && !(1 in [1]) && 'length' in []"), "true");
+        assert_eq!(
+            inspect("var a = [1, 2]; delete a[0]; a"),
+            "[ undefined, 2 ]"
+        );
+        assert_eq!(inspect("var a = []; a === a && [] !== []"), "true");
+        assert_eq!(inspect("[1, 2] == '1,2'"), "true");
+    }
+
+    #[test]
+    fn arrays_reject_other_properties() {
+        for (source, property) in [
+            ("var a = []; a.foo = 1", "foo"),
+            ("var a = []; a['01'] = 5", "01"),
+            ("var a = [1]; a[-1] = 2", "-1"),
+        ] {
+            let thrown = error(source);
+            assert_eq!(thrown.kind, ErrorKind::TypeError, "{}", source);
+            assert_eq!(
+                thrown.message,
+                format!("Cannot create property '{}' on an array", property)
+            );
+        }
+        assert_eq!(inspect("var a = [1]; a.foo"), "undefined");
+    }
+
+    #[test]
+    fn cyclic_arrays_convert_to_strings() {
+        assert_eq!(inspect("var a = [1]; a[0] = a; a + ''"), "''");
+        assert_eq!(inspect("var a = [1, 2]; a[0] = a; a + ''"), "',2'");
+        assert_eq!(
+            inspect("var a = [1]; var b = [a, 2]; a[0] = b; `${b}`"),
+            "',2'"
+        );
+        assert_eq!(inspect("var a = [1]; [a, a] + ''"), "'1,1'");
+    }
+
+    #[test]
+    fn invalid_array_lengths_throw() {
+        for source in [
+            "var a = []; a.length = -1",
+            "var a = []; a.length = 1.5",
+            "var a = []; a.length = 4294967296",
+            "var a = []; a.length = 'many'",
+            "var a = []; a.length = 4294967295",
+            "var a = []; a[4294967294] = 1",
+        ] {
+            let thrown = error(source);
+            assert_eq!(thrown.kind, ErrorKind::RangeError, "{}", source);
+            assert_eq!(thrown.message, "Invalid array length", "{}", source);
+        }
+    }
+}
+
+#[cfg(test)]
+mod object_tests {
+    use super::test_support::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn object_literals() {
+        assert_eq!(
+            inspect("({b: 1, 'a-b': [2], 3: {c: null}, 0.5: 1, if: 2})"),
+            "{ '3': { c: null }, b: 1, 'a-b': [ 2 ], '0.5': 1, if: 2 }"
+        );
+        assert_eq!(inspect("var a = 1, b = 'x'; ({a, b})"), "{ a: 1, b: 'x' }");
+        assert_eq!(
+            inspect("var k = 'key'; ({[k + 1]: 1, [[1, 2]]: 2})"),
+            "{ key1: 1, '1,2': 2 }"
+        );
+        assert_eq!(inspect("({a: 1, b: 2, a: 3})"), "{ a: 3, b: 2 }");
+        assert_eq!(
+            inspect("var o = {a: 1, b: 2}; delete o.a; o.a = 3; o"),
+            "{ b: 2, a: 3 }"
+        );
+        assert_eq!(inspect("({}) + ''"), "'[object Object]'");
+        assert_eq!(inspect("typeof {}"), "'object'");
+    }
+
+    #[test]
+    fn methods_see_the_object_as_this() {
+        assert_eq!(
+            inspect("var o = {n: 2, twice(x) { return this.n * x; }}; o.twice(3) + o['twice'](4)"),
+            "14"
+        );
+        assert_eq!(
+            inspect("var o = {m() {}, f: function () {}, a: () => 1, g: function h() {}}; [o.m.name, o.f.name, o.a.name, o.g.name]"),
+            "[ 'm', 'f', 'a', 'h' ]"
+        );
+        assert_eq!(inspect("({['x' + 1]() {}}).x1.name"), "'x1'");
+    }
+
+    #[test]
+    fn spread_copies_properties() {
+        assert_eq!(
+            inspect("var a = {x: 1, y: 2}; ({...a, y: 3, z: 4})"),
+            "{ x: 1, y: 3, z: 4 }"
+        );
+        assert_eq!(inspect("({y: 3, ...{y: 4}})"), "{ y: 4 }");
+        assert_eq!(inspect("({...[1, 2], ...'ab'})"), "{ '0': 'a', '1': 'b' }");
+        assert_eq!(inspect("({...null, ...undefined, ...1, ...true})"), "{}");
+        assert_eq!(
+            inspect("var a = {x: {}}; var b = {...a}; b.y = 1; a.x === b.x && !('y' in a)"),
+            "true"
+        );
+    }
+
+    #[test]
+    fn member_access_and_assignment() {
+        assert_eq!(
+            inspect("var o = {a: {b: [1]}}; o.a.b[0] + o['a']['b'].length"),
+            "2"
+        );
+        assert_eq!(inspect("var o = {}; o.missing"), "undefined");
+        assert_eq!(inspect("var o = {}; o['missing'] === undefined"), "true");
+        assert_eq!(
+            inspect("var o = {}; o.a = 1; o['b'] = {}; o.b.c = o.a + 1; o"),
+            "{ a: 1, b: { c: 2 } }"
+        );
+        assert_eq!(
+            inspect("var o = {n: 1}; o.n += 2; o['n'] *= 2; o.n++; o.n"),
+            "7"
+        );
+        assert_eq!(inspect("var o = {}; o[1 + 1] = 'two'; o['2']"), "'two'");
+    }
+
+    #[test]
+    fn primitive_properties() {
+        assert_eq!(inspect("'abc'.length"), "3");
+        assert_eq!(inspect("var s = 'abc'; s[0] + s['2'] + s[1]"), "'acb'");
+        assert_eq!(inspect("'abc'[3]"), "undefined");
+        assert_eq!(inspect("''.length"), "0");
+        assert_eq!(inspect("'é😀'.length"), "3");
+        assert_eq!(inspect("`${'x'.length}`"), "'1'");
+        assert_eq!(inspect("(1).length"), "undefined");
+        assert_eq!(inspect("true.x"), "undefined");
+    }
+
+    #[test]
+    fn missing_objects_throw() {
+        for (source, message) in [
+            (
+                "var o = {}; o.a.b",
+                "Cannot read properties of undefined (reading 'b')",
+            ),
+            (
+                "var o = {a: null}; o.a.b = 1",
+                "Cannot set properties of null (setting 'b')",
+            ),
+            ("var o = {}; o.f()", "o.f is not a function"),
+        ] {
+            let error = error(source);
+            assert_eq!(error.kind, ErrorKind::TypeError, "{}", source);
+            assert_eq!(error.message, message, "{}", source);
+        }
     }
 }
@@ -2,3 +2,57 @@ pub const KW_VAR: &str = "var";
 pub const KW_CONST: &str = "const";
 pub const KW_LET: &str = "let";
 pub const KW_FUNCTION: &str = "function";
+pub const KW_RETURN: &str = "return";
+pub const KW_IF: &str = "if";
+pub const KW_ELSE: &str = "else";
+pub const KW_WHILE: &str = "while";
+pub const KW_DO: &str = "do";
+pub const KW_FOR: &str = "for";
+pub const KW_BREAK: &str = "break";
+pub const KW_CONTINUE: &str = "continue";
+pub const KW_TYPEOF: &str = "typeof";
+pub const KW_VOID: &str = "void";
+pub const KW_DELETE: &str = "delete";
+pub const KW_IN: &str = "in";
+pub const KW_INSTANCEOF: &str = "instanceof";
+
+/// Every word the lexer reports as a `TokenKind::Keyword` rather than an identifier.
+pub const KEYWORDS: &[&str] = &[
+    KW_BREAK,
+    "case",
+    "catch",
+    "class",
+    KW_CONST,
+    KW_CONTINUE,
+    "debugger",
+    "default",
+    KW_DELETE,
+    KW_DO,
+    KW_ELSE,
+    "export",
+    "extends",
+    "false",
+    "finally",
+    KW_FOR,
+    KW_FUNCTION,
+    KW_IF,
+    "import",
+    KW_IN,
+    KW_INSTANCEOF,
+    KW_LET,
+    "new",
+    "null",
+    KW_RETURN,
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    KW_TYPEOF,
+    KW_VAR,
+    KW_VOID,
+    KW_WHILE,
+    "with",
+    "yield",
+];
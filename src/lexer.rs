@@ -0,0 +1,854 @@
+use std::fmt;
+
+use crate::error::JsError;
+use crate::interpreter_2::MAX_NESTING_DEPTH;
+use crate::keywords::KEYWORDS;
+use crate::operators::PUNCTUATORS;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Identifier(String),
+    Keyword(&'static str),
+    Punctuator(&'static str),
+    Number(f64),
+    String(String),
+    /// The raw source between the backticks, substitutions included.
+    Template(String),
+    Comment(String),
+    LineTerminator,
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use TokenKind::*;
+        match self {
+            Identifier(name) => write!(f, "{}", name),
+            Keyword(keyword) => write!(f, "{}", keyword),
+            Punctuator(punctuator) => write!(f, "{}", punctuator),
+            Number(number) => write!(f, "{}", number),
+            String(string) => write!(f, "{:?}", string),
+            Template(raw) => write!(f, "`{}`", raw),
+            Comment(comment) => write!(f, "{}", comment),
+            LineTerminator => write!(f, "line terminator"),
+            Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+/// A 1-based line and column in the source, counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A token along with the byte offsets of the source it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+    pub position: Position,
+}
+
+/// A run of literal text in a template literal, ending at a substitution or the closing
+/// backtick.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateString {
+    /// The text with its escapes decoded, or the error for an invalid escape. Only tagged
+    /// templates accept invalid escapes.
+    pub cooked: Result<String, String>,
+    pub raw: String,
+    /// Whether a `${` substitution follows.
+    pub substitution: bool,
+}
+
+pub struct Lexer<'a> {
+    source: &'a str,
+    current: usize,
+    position: Position,
+    /// How many template literals the current token is nested in.
+    templates: usize,
+}
+
+fn is_line_terminator(c: char) -> bool {
+    ['\n', '\r', '\u{2028}', '\u{2029}'].contains(&c)
+}
+
+fn is_whitespace(c: char) -> bool {
+    !is_line_terminator(c) && (c.is_whitespace() || c == '\u{feff}')
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_identifier_part(c: char) -> bool {
+    is_identifier_start(c) || c.is_alphanumeric()
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            current: 0,
+            position: Position { line: 1, column: 1 },
+            templates: 0,
+        }
+    }
+
+    /// Starts lexing part way through `source`, at byte `offset`, which is at `position`.
+    pub fn resume(source: &'a str, offset: usize, position: Position) -> Self {
+        Self {
+            source,
+            current: offset,
+            position,
+            templates: 0,
+        }
+    }
+
+    /// Reads every token in `source`, finishing with a single `TokenKind::Eof`.
+    pub fn tokenize(source: &'a str) -> Result<Vec<Token>, JsError> {
+        let mut lexer = Lexer::new(source);
+        let mut tokens = Vec::new();
+        loop {
+            let token = lexer.next_token()?;
+            let done = token.kind == TokenKind::Eof;
+            tokens.push(token);
+            if done {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    /// Gets the next character but doesn't consume it.
+    fn lookahead(&self) -> Option<char> {
+        self.source[self.current..].chars().next()
+    }
+
+    /// Gets the character after the next one without consuming anything.
+    fn peek_second(&self) -> Option<char> {
+        self.source[self.current..].chars().nth(1)
+    }
+
+    /// Consumes the next character and returns it.
+    fn current(&mut self) -> char {
+        let current = self.lookahead().unwrap_or('\0');
+        self.current += current.len_utf8();
+        if is_line_terminator(current) && !(current == '\r' && self.lookahead() == Some('\n')) {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        current
+    }
+
+    fn matches(&self, predicate: impl Fn(char) -> bool) -> bool {
+        self.lookahead().is_some_and(predicate)
+    }
+
+    fn matches_second(&self, predicate: impl Fn(char) -> bool) -> bool {
+        self.peek_second().is_some_and(predicate)
+    }
+
+    fn whitespace(&mut self) {
+        while self.matches(is_whitespace) {
+            self.current();
+        }
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, JsError> {
+        self.whitespace();
+        let start = self.current;
+        let position = self.position;
+        let kind = match (self.lookahead(), self.peek_second()) {
+            (None, _) => Ok(TokenKind::Eof),
+            (Some(c), _) if is_line_terminator(c) => Ok(self.line_terminator()),
+            (Some('/'), Some('/')) => Ok(self.line_comment()),
+            (Some('/'), Some('*')) => self.block_comment(),
+            (Some(c), _) if c.is_ascii_digit() => self.number(),
+            (Some('.'), Some(c)) if c.is_ascii_digit() => self.number(),
+            (Some('"'), _) | (Some('\''), _) => self.string(),
+            (Some('`'), _) => self.template(),
+            (Some(c), _) if is_identifier_start(c) => Ok(self.identifier()),
+            (Some(_), _) => self.punctuator(),
+        };
+
+        match kind {
+            Ok(kind) => Ok(Token {
+                kind,
+                start,
+                end: self.current,
+                position,
+            }),
+            Err(message) => Err(JsError::syntax(message, position)),
+        }
+    }
+
+    fn line_terminator(&mut self) -> TokenKind {
+        if self.current() == '\r' && self.lookahead() == Some('\n') {
+            self.current();
+        }
+        TokenKind::LineTerminator
+    }
+
+    fn line_comment(&mut self) -> TokenKind {
+        let start = self.current;
+        while self.lookahead().is_some() && !self.matches(is_line_terminator) {
+            self.current();
+        }
+        TokenKind::Comment(String::from(&self.source[start..self.current]))
+    }
+
+    fn block_comment(&mut self) -> Result<TokenKind, String> {
+        let start = self.current;
+        self.current();
+        self.current();
+        while !self.source[self.current..].starts_with("*/") {
+            if self.lookahead().is_none() {
+                return Err(String::from("Unterminated comment"));
+            }
+            self.current();
+        }
+        self.current();
+        self.current();
+        Ok(TokenKind::Comment(String::from(
+            &self.source[start..self.current],
+        )))
+    }
+
+    /// Consumes a run of digits in `radix`, which may contain single underscores between
+    /// digits as numeric separators.
+    fn digits(&mut self, radix: u32) -> Result<(), String> {
+        while self.matches(|c| c.is_digit(radix)) {
+            self.current();
+            if self.lookahead() == Some('_') {
+                self.current();
+                match self.lookahead() {
+                    Some('_') => {
+                        return Err(String::from(
+                            "Only one underscore is allowed as numeric separator",
+                        ))
+                    }
+                    Some(c) if c.is_digit(radix) => (),
+                    _ => {
+                        return Err(String::from(
+                            "Numeric separators are not allowed at the end of numeric literals",
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<TokenKind, String> {
+        let start = self.current;
+        let radix = match (self.lookahead(), self.peek_second()) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('o' | 'O')) => 8,
+            (Some('0'), Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+        let number = if radix != 10 {
+            self.current();
+            self.current();
+            let digits = self.current;
+            self.digits(radix)?;
+            if self.current == digits {
+                return Err(String::from("Invalid or unexpected token"));
+            }
+            self.source[digits..self.current]
+                .chars()
+                .filter_map(|c| c.to_digit(radix))
+                .fold(0f64, |number, digit| number * radix as f64 + digit as f64)
+        } else if self.lookahead() == Some('0')
+            && self.matches_second(|c| c.is_ascii_digit() || c == '_')
+        {
+            self.legacy_octal()?
+        } else {
+            self.decimal(start)?
+        };
+        // A number can't run straight into an identifier or another number, as in `3in` or
+        // `0b12`.
+        if self.matches(|c| is_identifier_start(c) || c.is_ascii_digit()) {
+            return Err(String::from("Invalid or unexpected token"));
+        }
+        Ok(TokenKind::Number(number))
+    }
+
+    /// Reads the rest of a decimal literal starting at `start`, which may have a fraction, an
+    /// exponent or both.
+    fn decimal(&mut self, start: usize) -> Result<f64, String> {
+        self.digits(10)?;
+        if self.lookahead() == Some('.') {
+            self.current();
+            self.digits(10)?;
+        }
+        if self.matches(|c| c == 'e' || c == 'E') {
+            self.current();
+            if self.matches(|c| c == '+' || c == '-') {
+                self.current();
+            }
+            if !self.matches(|c| c.is_ascii_digit()) {
+                return Err(String::from("Invalid or unexpected token"));
+            }
+            self.digits(10)?;
+        }
+        let number = self.source[start..self.current].replace('_', "");
+        number
+            .parse()
+            .map_err(|_| format!("Invalid number {}", number))
+    }
+
+    /// Reads a number with a leading zero such as `017`, which is octal if every digit is
+    /// below 8 and otherwise decimal. The parser rejects both in strict mode.
+    fn legacy_octal(&mut self) -> Result<f64, String> {
+        let start = self.current;
+        while self.matches(|c| c.is_ascii_digit()) {
+            self.current();
+        }
+        if self.lookahead() == Some('_') {
+            return Err(String::from(
+                "Numeric separator can not be used after leading 0.",
+            ));
+        }
+        let digits = &self.source[start..self.current];
+        if digits.chars().all(|c| c < '8') {
+            return Ok(digits.chars().fold(0f64, |number, digit| {
+                number * 8f64 + digit.to_digit(8).unwrap_or(0) as f64
+            }));
+        }
+        self.decimal(start)
+    }
+
+    fn string(&mut self) -> Result<TokenKind, String> {
+        let quote = self.current();
+        let mut string = String::new();
+        loop {
+            match self.lookahead() {
+                Some(c) if c == quote => break,
+                Some('\\') if self.peek_second().is_some() => {
+                    self.current();
+                    string.extend(self.escape()?);
+                }
+                // Unlike the other line terminators, U+2028 and U+2029 may appear in strings.
+                Some(c) if c != '\\' && c != '\n' && c != '\r' => string.push(self.current()),
+                _ => return Err(String::from("Unterminated string literal")),
+            }
+        }
+        self.current();
+        Ok(TokenKind::String(string))
+    }
+
+    /// Reads the escape sequence after a backslash, giving the character it stands for, or
+    /// `None` for a line continuation.
+    fn escape(&mut self) -> Result<Option<char>, String> {
+        let c = match self.current() {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'v' => '\u{b}',
+            '0' if !self.matches(|c| c.is_ascii_digit()) => '\0',
+            // A legacy octal escape such as `\251`, which can't go above `\377`.
+            c @ '0'..='7' => {
+                let mut value = c.to_digit(8).unwrap_or(0);
+                let length = if c <= '3' { 3 } else { 2 };
+                for _ in 1..length {
+                    match self.lookahead().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            self.current();
+                            value = value * 8 + digit;
+                        }
+                        None => break,
+                    }
+                }
+                char::from_u32(value).unwrap_or('\u{fffd}')
+            }
+            'x' => self
+                .hex_digits(2)
+                .and_then(char::from_u32)
+                .ok_or("Invalid hexadecimal escape sequence")?,
+            'u' => self.unicode_escape()?,
+            '\r' => {
+                if self.lookahead() == Some('\n') {
+                    self.current();
+                }
+                return Ok(None);
+            }
+            c if is_line_terminator(c) => return Ok(None),
+            c => c,
+        };
+        Ok(Some(c))
+    }
+
+    /// Consumes exactly `count` hex digits and returns their value.
+    fn hex_digits(&mut self, count: usize) -> Option<u32> {
+        let digits = self.source[self.current..].get(..count)?;
+        let value = match digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            true => u32::from_str_radix(digits, 16).ok()?,
+            false => return None,
+        };
+        for _ in 0..count {
+            self.current();
+        }
+        Some(value)
+    }
+
+    /// Reads the rest of a `\uXXXX` or `\u{X...}` escape. A surrogate pair written as two
+    /// escapes becomes one character, and a lone surrogate becomes U+FFFD since Rust strings
+    /// can't hold it.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let invalid = || String::from("Invalid Unicode escape sequence");
+        if self.lookahead() == Some('{') {
+            self.current();
+            let start = self.current;
+            while self.matches(|c| c.is_ascii_hexdigit()) {
+                self.current();
+            }
+            let digits = &self.source[start..self.current];
+            if digits.is_empty() || self.lookahead() != Some('}') {
+                return Err(invalid());
+            }
+            self.current();
+            return match u32::from_str_radix(digits, 16) {
+                Ok(value) if value <= 0x10ffff => Ok(char::from_u32(value).unwrap_or('\u{fffd}')),
+                _ => Err(String::from("Undefined Unicode code-point")),
+            };
+        }
+        let value = self.hex_digits(4).ok_or_else(invalid)?;
+        if (0xd800..0xdc00).contains(&value) && self.source[self.current..].starts_with("\\u") {
+            let low = self.source[self.current + 2..]
+                .get(..4)
+                .and_then(|digits| u32::from_str_radix(digits, 16).ok());
+            if let Some(low @ 0xdc00..0xe000) = low {
+                for _ in 0..6 {
+                    self.current();
+                }
+                let value = 0x10000 + ((value - 0xd800) << 10) + (low - 0xdc00);
+                return Ok(char::from_u32(value).unwrap_or('\u{fffd}'));
+            }
+        }
+        Ok(char::from_u32(value).unwrap_or('\u{fffd}'))
+    }
+
+    fn template(&mut self) -> Result<TokenKind, String> {
+        // Each nested template is read recursively through `substitution`.
+        if self.templates == MAX_NESTING_DEPTH {
+            return Err(String::from("Template literals are nested too deeply"));
+        }
+        self.current();
+        let start = self.current;
+        self.templates += 1;
+        while self.template_string()?.substitution {
+            self.substitution()?;
+        }
+        self.templates -= 1;
+        let raw = String::from(&self.source[start..self.current - 1]);
+        Ok(TokenKind::Template(raw))
+    }
+
+    /// Reads a template's literal text up to and including the `${` or closing backtick that
+    /// ends it.
+    pub fn template_string(&mut self) -> Result<TemplateString, String> {
+        let start = self.current;
+        let mut cooked = Ok(String::new());
+        let substitution = loop {
+            let c = match self.lookahead() {
+                Some('`') => {
+                    self.current();
+                    break false;
+                }
+                Some('$') if self.peek_second() == Some('{') => {
+                    self.current();
+                    self.current();
+                    break true;
+                }
+                None => return Err(String::from("Unterminated template literal")),
+                Some('\\') if self.peek_second().is_none() => {
+                    return Err(String::from("Unterminated template literal"))
+                }
+                // Templates don't allow legacy octal escapes, so `\0` can't be followed by a
+                // digit.
+                Some('\\') if self.matches_second(|c| c.is_ascii_digit()) => {
+                    self.current();
+                    match self.current() {
+                        '0' if !self.matches(|c| c.is_ascii_digit()) => Ok(Some('\0')),
+                        '8' | '9' => Err(String::from(
+                            "\\8 and \\9 are not allowed in template strings",
+                        )),
+                        _ => Err(String::from(
+                            "Octal escape sequences are not allowed in template strings",
+                        )),
+                    }
+                }
+                Some('\\') => {
+                    self.current();
+                    self.escape()
+                }
+                // Line breaks in templates are normalized to `\n`.
+                Some('\r') => {
+                    self.line_terminator();
+                    Ok(Some('\n'))
+                }
+                Some(_) => Ok(Some(self.current())),
+            };
+            // Keep scanning after an invalid escape, since a tagged template can still see the
+            // raw text.
+            match (&mut cooked, c) {
+                (Ok(cooked), Ok(c)) => cooked.extend(c),
+                (Ok(_), Err(message)) => cooked = Err(message),
+                _ => (),
+            }
+        };
+        let end = self.current - if substitution { 2 } else { 1 };
+        let raw = self.source[start..end]
+            .replace("\r\n", "\n")
+            .replace('\r', "\n");
+        Ok(TemplateString {
+            cooked,
+            raw,
+            substitution,
+        })
+    }
+
+    /// Reads the tokens of a `${ ... }` substitution up to and including its closing brace.
+    pub fn substitution(&mut self) -> Result<Vec<Token>, String> {
+        let mut tokens = Vec::new();
+        let mut depth = 1;
+        while depth > 0 {
+            let token = self.next_token().map_err(|error| error.message)?;
+            match token.kind {
+                TokenKind::Punctuator("{") => depth += 1,
+                TokenKind::Punctuator("}") => depth -= 1,
+                TokenKind::Eof => return Err(String::from("Unterminated template literal")),
+                _ => (),
+            }
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+
+    fn identifier(&mut self) -> TokenKind {
+        let start = self.current;
+        while self.matches(is_identifier_part) {
+            self.current();
+        }
+        let word = &self.source[start..self.current];
+        match KEYWORDS.iter().find(|keyword| **keyword == word) {
+            Some(keyword) => TokenKind::Keyword(keyword),
+            None => TokenKind::Identifier(String::from(word)),
+        }
+    }
+
+    fn punctuator(&mut self) -> Result<TokenKind, String> {
+        let rest = &self.source[self.current..];
+        match PUNCTUATORS
+            .iter()
+            .find(|punctuator| rest.starts_with(**punctuator))
+        {
+            Some(punctuator) => {
+                self.current += punctuator.len();
+                self.position.column += punctuator.len();
+                Ok(TokenKind::Punctuator(punctuator))
+            }
+            None => Err(format!("Unexpected character {}", self.current())),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token, JsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok(Token {
+                kind: TokenKind::Eof,
+                ..
+            }) => None,
+            Err(error) => {
+                // Skip the rest of the source so iteration stops after the first error.
+                self.current = self.source.len();
+                Some(Err(error))
+            }
+            token => Some(token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod lexer_tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        Lexer::new(source)
+            .map(|token| token.unwrap().kind)
+            .collect()
+    }
+
+    #[test]
+    fn lex_assignment() {
+        assert_eq!(
+            kinds("var jake = 26;"),
+            vec![
+                TokenKind::Keyword("var"),
+                TokenKind::Identifier(String::from("jake")),
+                TokenKind::Punctuator("="),
+                TokenKind::Number(26f64),
+                TokenKind::Punctuator(";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn punctuators_are_longest_first() {
+        for (index, shorter) in PUNCTUATORS.iter().enumerate() {
+            for longer in &PUNCTUATORS[index + 1..] {
+                assert!(
+                    !longer.starts_with(shorter),
+                    "{} shadows {}",
+                    shorter,
+                    longer
+                );
+            }
+        }
+        assert_eq!(kinds(">>>=")[0], TokenKind::Punctuator(">>>="));
+    }
+
+    #[test]
+    fn lex_offsets() {
+        let tokens = Lexer::tokenize("let é = 1").unwrap();
+        let offsets: Vec<(usize, usize)> = tokens.iter().map(|t| (t.start, t.end)).collect();
+        assert_eq!(offsets, vec![(0, 3), (4, 6), (7, 8), (9, 10), (10, 10)]);
+    }
+
+    #[test]
+    fn lex_longest_punctuator() {
+        assert_eq!(
+            kinds("a >>>= b === c"),
+            vec![
+                TokenKind::Identifier(String::from("a")),
+                TokenKind::Punctuator(">>>="),
+                TokenKind::Identifier(String::from("b")),
+                TokenKind::Punctuator("==="),
+                TokenKind::Identifier(String::from("c")),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_numbers() {
+        assert_eq!(
+            kinds("12 3.5 4..toString"),
+            vec![
+                TokenKind::Number(12f64),
+                TokenKind::Number(3.5f64),
+                TokenKind::Number(4f64),
+                TokenKind::Punctuator("."),
+                TokenKind::Identifier(String::from("toString")),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_numeric_literals() {
+        for (source, expected) in [
+            ("2.75", 2.75),
+            (".5", 0.5),
+            ("5.", 5f64),
+            ("1e10", 1e10),
+            ("2.5E-3", 0.0025),
+            ("1e+2", 100f64),
+            ("0xFF", 255f64),
+            ("0XfF", 255f64),
+            ("0o17", 15f64),
+            ("0b101", 5f64),
+            ("1_000_000", 1000000f64),
+            ("0x_F", f64::NAN),
+            ("0xFF_FF", 65535f64),
+            ("1_0.0_1e1_0", 10.01e10),
+            ("017", 15f64),
+            ("019", 19f64),
+            ("08.5", 8.5),
+            ("00", 0f64),
+        ] {
+            match Lexer::new(source).next_token() {
+                Ok(token) if !expected.is_nan() => {
+                    assert_eq!(token.kind, TokenKind::Number(expected), "{}", source);
+                    assert_eq!(token.end, source.len(), "{}", source);
+                }
+                Ok(token) => panic!("{} lexed as {:?}", source, token),
+                Err(_) => assert!(expected.is_nan(), "{}", source),
+            }
+        }
+    }
+
+    #[test]
+    fn lex_invalid_numeric_literals() {
+        for (source, message) in [
+            (
+                "1__0",
+                "Only one underscore is allowed as numeric separator",
+            ),
+            (
+                "1_",
+                "Numeric separators are not allowed at the end of numeric literals",
+            ),
+            (
+                "1_.5",
+                "Numeric separators are not allowed at the end of numeric literals",
+            ),
+            ("0_1", "Numeric separator can not be used after leading 0."),
+            ("3in", "Invalid or unexpected token"),
+            ("0b12", "Invalid or unexpected token"),
+            ("0x", "Invalid or unexpected token"),
+            ("1e", "Invalid or unexpected token"),
+            ("4.toString", "Invalid or unexpected token"),
+        ] {
+            let error = Lexer::tokenize(source).unwrap_err();
+            assert_eq!(error.message, message, "{}", source);
+        }
+    }
+
+    #[test]
+    fn lex_strings() {
+        assert_eq!(
+            kinds("\"jake\" 'it\\'s'"),
+            vec![
+                TokenKind::String(String::from("jake")),
+                TokenKind::String(String::from("it's")),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_escape_sequences() {
+        for (source, expected) in [
+            (r#"'a\nb\tc\rd'"#, "a\nb\tc\rd"),
+            (r#"'\b\f\v\0'"#, "\u{8}\u{c}\u{b}\0"),
+            (r#""say \"hi\"""#, "say \"hi\""),
+            (r#"'it\'s \\ \q'"#, "it's \\ q"),
+            (r#"'\x41\x7a'"#, "Az"),
+            (r#"'A\u{42}\u{1F600}'"#, "AB\u{1F600}"),
+            (r#"'😀'"#, "\u{1F600}"),
+            (r#"'\uD83D!'"#, "\u{fffd}!"),
+            (r#"'\101\60\7\400'"#, "A0\u{7}\u{20}0"),
+            (r#"'\8\9'"#, "89"),
+            ("'a\\\nb\\\r\nc'", "abc"),
+            ("'a\u{2028}b'", "a\u{2028}b"),
+            ("\"it's\"", "it's"),
+        ] {
+            assert_eq!(
+                kinds(source),
+                vec![TokenKind::String(String::from(expected))],
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn lex_invalid_escape_sequences() {
+        for (source, message) in [
+            (r"'\x4'", "Invalid hexadecimal escape sequence"),
+            (r"'\xZZ'", "Invalid hexadecimal escape sequence"),
+            (r"'\u12'", "Invalid Unicode escape sequence"),
+            (r"'\u{}'", "Invalid Unicode escape sequence"),
+            (r"'\u{41'", "Invalid Unicode escape sequence"),
+            (r"'\u{110000}'", "Undefined Unicode code-point"),
+            ("'a\nb'", "Unterminated string literal"),
+            ("'a\\", "Unterminated string literal"),
+            ("\"a'", "Unterminated string literal"),
+        ] {
+            let error = Lexer::tokenize(source).unwrap_err();
+            assert_eq!(error.message, message, "{}", source);
+        }
+    }
+
+    #[test]
+    fn lex_template() {
+        assert_eq!(
+            kinds("`a ${ {b: `${c}`}.b } d`;"),
+            vec![
+                TokenKind::Template(String::from("a ${ {b: `${c}`}.b } d")),
+                TokenKind::Punctuator(";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_template_strings() {
+        let source = "`a\\x41${b}\\8\r\nc${ {} }`";
+        let mut lexer = Lexer::resume(source, 1, Position { line: 1, column: 2 });
+        let string = lexer.template_string().unwrap();
+        assert_eq!(string.cooked, Ok(String::from("aA")));
+        assert_eq!(string.raw, "a\\x41");
+        assert!(string.substitution);
+        let tokens: Vec<TokenKind> = lexer
+            .substitution()
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                TokenKind::Identifier(String::from("b")),
+                TokenKind::Punctuator("}")
+            ]
+        );
+        let string = lexer.template_string().unwrap();
+        assert_eq!(
+            string.cooked,
+            Err(String::from(
+                "\\8 and \\9 are not allowed in template strings"
+            ))
+        );
+        assert_eq!(string.raw, "\\8\nc");
+        let tokens = lexer.substitution().unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].position, Position { line: 2, column: 5 });
+        let string = lexer.template_string().unwrap();
+        assert_eq!(string.cooked, Ok(String::new()));
+        assert!(!string.substitution);
+    }
+
+    #[test]
+    fn lex_comments_and_line_terminators() {
+        assert_eq!(
+            kinds("a // one\r\n/* two */ b"),
+            vec![
+                TokenKind::Identifier(String::from("a")),
+                TokenKind::Comment(String::from("// one")),
+                TokenKind::LineTerminator,
+                TokenKind::Comment(String::from("/* two */")),
+                TokenKind::Identifier(String::from("b")),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_positions() {
+        let tokens = Lexer::tokenize("a\r\n  bé >>= c\n\nd").unwrap();
+        let positions: Vec<(usize, usize)> = tokens
+            .iter()
+            .filter(|token| token.kind != TokenKind::LineTerminator)
+            .map(|token| (token.position.line, token.position.column))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![(1, 1), (2, 3), (2, 6), (2, 10), (4, 1), (4, 2)]
+        );
+    }
+
+    #[test]
+    fn lex_unterminated_string() {
+        let error = Lexer::tokenize("var a = 1;\nvar b = 'jake").unwrap_err();
+        assert_eq!(error.message, "Unterminated string literal");
+        assert_eq!(error.position, Some(Position { line: 2, column: 9 }));
+    }
+}
@@ -0,0 +1,32 @@
+//! A recursive descent interpreter for JavaScript.
+//!
+//! ```
+//! use rustjs::{Context, Num, Value};
+//!
+//! let mut context = Context::new();
+//! context.set_global("base", Value::Number(Num::F64(40.0)));
+//! let result = context.eval("var answer = base + 2; answer").unwrap();
+//! assert_eq!(result.to_string(), "42");
+//! assert!(context.get_global("answer").is_some());
+//! ```
+
+mod ast;
+mod builtins;
+mod context;
+mod environment;
+pub mod error;
+mod interpreter_2;
+mod keywords;
+mod lexer;
+mod operators;
+mod parser;
+pub mod types;
+
+pub use context::Context;
+pub use error::{ErrorKind, JsError};
+pub use interpreter_2::{MAX_CALL_DEPTH, MAX_NESTING_DEPTH, STACK_SIZE};
+pub use lexer::Position;
+pub use types::{Function, Num, Properties};
+
+/// A JavaScript value as seen by code embedding the interpreter.
+pub type Value = types::Type;
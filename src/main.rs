@@ -1,365 +1,137 @@
-use std::collections::HashMap;
-
-mod interpreter_2;
-mod keywords;
-mod operators;
-mod types;
-
-enum Operator {
-    Equals,
-    Add,
-}
-
-#[derive(Debug)]
-enum Types {
-    Null,
-    Undefined,
-    Number(f64),
-    Boolean(bool),
-    TextString(String),
-    Object(HashMap<String, Types>),
-    Function(String),
-}
-
-#[derive(Debug)]
-struct Function {
-    arguments: HashMap<String, Types>,
-    code: String,
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::process;
+use std::thread;
+
+mod repl;
+
+use rustjs::Context;
+
+const USAGE: &str = "Usage: rustjs [file.js | -e <code> | -i | -]
+
+  file.js      run the script in file.js
+  -e <code>    run <code>
+  -i           start the interactive REPL (the default when stdin is a terminal)
+  -            read the script from stdin";
+
+#[derive(Debug, PartialEq)]
+enum Input {
+    File(String),
+    Eval(String),
+    Stdin,
+    Repl,
+    Help,
 }
 
-struct Interpreter {
-    source: Vec<char>,
-    counter: usize,
-    lookahead: Option<char>,
-    lookahead_counter: usize,
-    lookup_table: HashMap<String, Types>,
-    scope_table: HashMap<String, Function>,
-    scope_index: usize,
-}
-
-impl Interpreter {
-    fn new(source: Vec<char>) -> Self {
-        Self {
-            source,
-            counter: 0,
-            lookahead: None,
-            lookahead_counter: 0,
-            lookup_table: HashMap::new(),
-            scope_table: HashMap::new(),
-            scope_index: 0,
-        }
-    }
-
-    fn get_char(&mut self) -> Option<char> {
-        self.counter += 1;
-        if self.source[self.counter] == '\x03' {
-            return None;
-        }
-        self.lookahead = Some(self.source[self.counter]);
-        println!("{:?}", self.lookahead);
-        self.lookahead
-    }
-
-    fn match_char(&mut self, c: char) {
-        match self.lookahead {
-            Some(l) => {
-                if l != c {
-                    panic!("Expected {} found {}", c, l)
-                }
-            }
-            None => panic!("Expected {} found nothing!", c),
-        }
-
-        if self.counter < self.source.len() - 1 {
-            self.get_char();
-        }
-    }
-
-    fn optionally_match_char(&mut self, c: char) {
-        if self.get_lookahead() == c {
-            self.match_char(c);
-        }
-    }
-
-    fn whitespace(&mut self) {
-        while self.lookahead == Some(' ') {
-            self.get_char();
-        }
-    }
-
-    fn keyword(&mut self, keyword: &str) {
-        for c in keyword.chars() {
-            self.match_char(c);
-        }
-
-        self.whitespace();
-    }
-
-    fn get_lookahead(&mut self) -> char {
-        match self.lookahead {
-            Some(c) => c,
-            None => panic!("End of input!"),
-        }
-    }
-
-    fn lookahead_is_not(&mut self, chars: &[char]) -> bool {
-        for c in chars {
-            if self.get_lookahead() == *c {
-                return false;
-            }
-        }
-
-        true
-    }
-
-    fn ident(&mut self) -> String {
-        let mut ident = String::new();
-        while self.lookahead_is_not(&[' ', ';', ',', '(']) {
-            ident.push(self.get_lookahead());
-            self.get_char();
-        }
-        self.whitespace();
-        ident
-    }
-
-    fn operator(&mut self, operator: Operator) {
-        use Operator::*;
-        let mut op = String::new();
-        while self.get_lookahead() != ' ' {
-            op.push(self.get_lookahead());
-            self.get_char();
-        }
-
-        match operator {
-            Equals => {
-                if op != String::from("=") {
-                    panic!("Expected operator '=' found {}", op);
-                }
-            }
-            Add => {
-                if op != String::from("+") {
-                    panic!("Expected operator '+' found {}", op);
-                }
-            }
-        }
-
-        self.whitespace();
-    }
-
-    fn get_digit(&mut self) -> char {
-        match self.lookahead {
-            Some(l) => {
-                if l.is_digit(10) {
-                    l
-                } else {
-                    panic!("Expected number found {}", l)
-                }
-            }
-            None => panic!("Expected number found nothing!"),
-        }
+fn parse_args(args: &[String]) -> Result<Input, String> {
+    match args {
+        [] => Ok(Input::Stdin),
+        [flag] if flag == "-" => Ok(Input::Stdin),
+        [flag] if flag == "-h" || flag == "--help" => Ok(Input::Help),
+        [flag] if flag == "-i" || flag == "--interactive" => Ok(Input::Repl),
+        [flag] if flag == "-e" || flag == "--eval" => Err(format!("{} requires an argument", flag)),
+        [flag, code] if flag == "-e" || flag == "--eval" => Ok(Input::Eval(code.clone())),
+        [file] if !file.starts_with('-') => Ok(Input::File(file.clone())),
+        _ => Err(format!("Unexpected arguments: {}", args.join(" "))),
     }
+}
 
-    fn number(&mut self) -> f64 {
-        let mut num = String::new();
-        while self.get_lookahead() != ' ' && self.get_lookahead() != ';' {
-            num.push(self.get_digit());
-            self.get_char();
+/// Runs `source` and reports any uncaught error on stderr, returning the process exit code.
+fn run(source: &str) -> i32 {
+    let mut context = Context::new();
+    match context.eval(source) {
+        Ok(_) => 0,
+        Err(error) => {
+            eprintln!("Uncaught {}", error);
+            eprint!("{}", error.code_frame(source));
+            1
         }
-
-        self.whitespace();
-        num.parse().unwrap()
-    }
-
-    fn eol(&mut self) {
-        self.match_char(';');
-        self.whitespace();
     }
+}
 
-    fn is_digit(&mut self) -> bool {
-        if let Some(c) = self.lookahead {
-            c.is_digit(10)
-        } else {
-            false
+/// Runs the command line and returns the process exit code.
+fn cli(args: &[String]) -> i32 {
+    let source = match parse_args(args) {
+        Ok(Input::Help) => {
+            println!("{}", USAGE);
+            return 0;
+        }
+        Ok(Input::Repl) => return repl::run(),
+        Ok(Input::Stdin) if args.is_empty() && io::stdin().is_terminal() => return repl::run(),
+        Ok(Input::Eval(code)) => Ok(code),
+        Ok(Input::File(file)) => fs::read_to_string(&file).map_err(|e| format!("{}: {}", file, e)),
+        Ok(Input::Stdin) => {
+            let mut source = String::new();
+            io::stdin()
+                .read_to_string(&mut source)
+                .map(|_| source)
+                .map_err(|e| format!("stdin: {}", e))
+        }
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return 2;
         }
-    }
-
-    fn matches_char(&mut self, c: char) -> bool {
-        self.get_lookahead() == c
-    }
-
-    fn is_alpha(&mut self) -> bool {
-        self.get_lookahead().is_alphabetic()
-    }
-
-    fn string(&mut self) {}
-
-    fn add(&mut self) -> Option<f64> {
-        self.lookahead_counter = self.counter;
-        let first_ident = self.ident();
-        if !self.matches_char('+') {
-            None
-        } else {
-            self.operator(Operator::Add);
-            let second_ident = self.ident();
-            let a = &self.lookup_table[&first_ident];
-            let b = &self.lookup_table[&second_ident];
+    };
 
-            match (a, b) {
-                (Types::Number(a_), Types::Number(b_)) => Some(a_ + b_),
-                _ => panic!("NOT A VALID ADDITION!"),
-            }
+    match source {
+        Ok(source) => run(&source),
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            1
         }
     }
+}
 
-    fn expression(&mut self) -> Types {
-        if self.is_digit() {
-            return Types::Number(self.number());
-        }
-
-        if self.matches_char('"') {
-            self.string();
-        }
-
-        if self.is_alpha() {
-            let result = self.add();
-            match result {
-                Some(res) => return Types::Number(res),
-                None => return Types::Undefined,
-            }
-        }
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    // Scripts run on their own thread so deep recursion reaches the interpreter's call limit
+    // rather than the main thread's smaller stack.
+    let code = thread::Builder::new()
+        .stack_size(rustjs::STACK_SIZE)
+        .spawn(move || cli(&args))
+        .expect("failed to start the interpreter thread")
+        .join()
+        .unwrap_or(101);
+    process::exit(code);
+}
 
-        if self.matches_char('(') {
-            return Types::Function(self.arrow_function());
-        }
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
 
-        Types::Undefined
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| String::from(*arg)).collect()
     }
 
-    fn arrow_function(&mut self) -> String {
-        self.match_char('(');
-        while self.get_lookahead() != ')' {
-            self.ident();
-        }
-        self.match_char(')');
-        self.whitespace();
-        self.match_char('=');
-        self.match_char('>');
-        self.whitespace();
-        self.match_char('{');
-        self.optionally_match_char('\n');
-        let mut function_body = String::new();
-        while self.lookahead_is_not(&['}']) {
-            match self.get_char() {
-                Some(c) => function_body.push(c),
-                None => panic!("End of stream"),
-            }
-        }
-        // self.whitespace();
-        // self.block();
-        self.match_char('}');
-        self.scope_table.insert(
-            self.scope_index.to_string(),
-            Function {
-                arguments: HashMap::new(),
-                code: function_body,
-            },
+    #[test]
+    fn parse_file() {
+        assert_eq!(
+            parse_args(&args(&["index.js"])),
+            Ok(Input::File(String::from("index.js")))
         );
-        let function_id = self.scope_index.to_string();
-        self.scope_index += 1;
-        function_id
-    }
-
-    fn statement(&mut self) {
-        self.keyword("var");
-        let ident = self.ident();
-        self.operator(Operator::Equals);
-        let value = self.expression();
-        println!("STATEMENT: {}, {:?}", ident, value);
-        self.lookup_table.insert(ident, value);
-        // match value {
-        //     Types::Number(_) => self.lookup_table.insert(ident, value),
-        //     Types::Function(_) => self.lookup_table.insert(ident, value),
-        //     _ => None,
-        // };
-        self.eol();
-    }
-
-    fn new_line(&mut self) {
-        if self.counter >= self.source.len() - 1 {
-            return;
-        }
-        self.match_char('\n');
-        self.whitespace();
-    }
-
-    fn init(&mut self) {
-        self.lookahead = Some(self.source[self.counter]);
     }
 
-    fn block(&mut self) {
-        while self.lookahead_is_not(&['}']) {
-            println!("{:?}", self.get_lookahead());
-            self.statement();
-            self.new_line();
-        }
+    #[test]
+    fn parse_eval() {
+        assert_eq!(
+            parse_args(&args(&["-e", "1 + 1"])),
+            Ok(Input::Eval(String::from("1 + 1")))
+        );
+        assert!(parse_args(&args(&["-e"])).is_err());
     }
 
-    fn is_function_call(&mut self) -> bool {
-        let mut ident = String::new();
-        let reset_counter = self.counter;
-        while self.lookahead_is_not(&[' ', ';', ',', '(']) {
-            ident.push(self.get_lookahead());
-            self.get_char();
-        }
-        let match_1 = self.matches_char('(');
-        self.get_char();
-        let match_2 = self.matches_char(')');
-        self.counter = reset_counter;
-        self.lookahead = Some(self.source[self.counter]);
-        ident.len() > 0 && match_1 && match_2
+    #[test]
+    fn parse_stdin() {
+        assert_eq!(parse_args(&args(&[])), Ok(Input::Stdin));
+        assert_eq!(parse_args(&args(&["-"])), Ok(Input::Stdin));
+        assert_eq!(parse_args(&args(&["-i"])), Ok(Input::Repl));
     }
 
-    fn function_call(&mut self) {
-        let name = self.ident();
-        self.match_char('(');
-        self.match_char(')');
-        self.eol();
-        println!("{:?}", self.lookup_table);
-        println!("{}", name);
-        println!("{:?}", &self.lookup_table["myFunc"]);
-        if let Types::Function(scope_id) = &self.lookup_table[&name] {
-            let scope: &Function = &self.scope_table[scope_id];
-            println!("scope: {:?}", scope);
-            let mut scope_interpreter = Interpreter::new(scope.code.chars().collect());
-            scope_interpreter.init();
-            scope_interpreter.whitespace();
-            scope_interpreter.program();
-        }
+    #[test]
+    fn run_exit_codes() {
+        assert_eq!(run("var a = 1;"), 0);
+        assert_eq!(run("var a = b;"), 1);
+        assert_eq!(run("var = 1;"), 1);
     }
-
-    fn program(&mut self) {
-        while self.lookahead_is_not(&['\x03', '}']) {
-            if self.is_function_call() {
-                self.function_call();
-            } else {
-                self.statement();
-            }
-            self.new_line();
-            self.whitespace();
-        }
-    }
-}
-
-fn main() {
-    let input = "var jakeVar = 10;
-    var b = 20;
-    var c = jakeVar + b;
-    var myFunc = () => {
-        var scopedVar = 10;
-    };
-    myFunc();
-    \x03";
-    let mut interpreter = Interpreter::new(input.chars().collect());
-    interpreter.init();
-    interpreter.program();
 }
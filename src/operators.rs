@@ -1,6 +1,117 @@
-pub const OP_ADD: char = '+';
-pub const OP_SUB: char = '-';
-pub const OP_MUL: char = '*';
-pub const OP_DIV: char = '/';
-pub const OP_MOD: char = '%';
-pub const OP_EQ: char = '=';
+pub const OP_ADD: &str = "+";
+pub const OP_SUB: &str = "-";
+pub const OP_MUL: &str = "*";
+pub const OP_DIV: &str = "/";
+pub const OP_MOD: &str = "%";
+pub const OP_EQ: &str = "=";
+pub const OP_AND: &str = "&&";
+pub const OP_OR: &str = "||";
+pub const OP_NULLISH: &str = "??";
+pub const OP_LT: &str = "<";
+pub const OP_GT: &str = ">";
+pub const OP_LT_EQ: &str = "<=";
+pub const OP_GT_EQ: &str = ">=";
+pub const OP_LOOSE_EQ: &str = "==";
+pub const OP_LOOSE_NOT_EQ: &str = "!=";
+pub const OP_STRICT_EQ: &str = "===";
+pub const OP_STRICT_NOT_EQ: &str = "!==";
+pub const OP_EXP: &str = "**";
+pub const OP_BIT_AND: &str = "&";
+pub const OP_BIT_OR: &str = "|";
+pub const OP_BIT_XOR: &str = "^";
+pub const OP_BIT_NOT: &str = "~";
+pub const OP_NOT: &str = "!";
+pub const OP_SHL: &str = "<<";
+pub const OP_SHR: &str = ">>";
+pub const OP_USHR: &str = ">>>";
+pub const OP_INCREMENT: &str = "++";
+pub const OP_DECREMENT: &str = "--";
+
+pub const OP_ADD_ASSIGN: &str = "+=";
+pub const OP_SUB_ASSIGN: &str = "-=";
+pub const OP_MUL_ASSIGN: &str = "*=";
+pub const OP_DIV_ASSIGN: &str = "/=";
+pub const OP_MOD_ASSIGN: &str = "%=";
+pub const OP_EXP_ASSIGN: &str = "**=";
+pub const OP_SHL_ASSIGN: &str = "<<=";
+pub const OP_SHR_ASSIGN: &str = ">>=";
+pub const OP_USHR_ASSIGN: &str = ">>>=";
+pub const OP_BIT_AND_ASSIGN: &str = "&=";
+pub const OP_BIT_OR_ASSIGN: &str = "|=";
+pub const OP_BIT_XOR_ASSIGN: &str = "^=";
+pub const OP_AND_ASSIGN: &str = "&&=";
+pub const OP_OR_ASSIGN: &str = "||=";
+pub const OP_NULLISH_ASSIGN: &str = "??=";
+pub const OP_ARROW: &str = "=>";
+pub const OP_SPREAD: &str = "...";
+pub const OP_CONDITIONAL: &str = "?";
+pub const OP_COLON: &str = ":";
+pub const OP_DOT: &str = ".";
+pub const OP_COMMA: &str = ",";
+pub const OP_SEMICOLON: &str = ";";
+pub const OP_OPEN_BRACE: &str = "{";
+pub const OP_CLOSE_BRACE: &str = "}";
+pub const OP_OPEN_PAREN: &str = "(";
+pub const OP_CLOSE_PAREN: &str = ")";
+pub const OP_OPEN_BRACKET: &str = "[";
+pub const OP_CLOSE_BRACKET: &str = "]";
+
+/// Every punctuator the lexer recognises, longest first so the first prefix match wins.
+pub const PUNCTUATORS: &[&str] = &[
+    OP_USHR_ASSIGN,
+    OP_SPREAD,
+    OP_STRICT_EQ,
+    OP_STRICT_NOT_EQ,
+    OP_EXP_ASSIGN,
+    OP_SHL_ASSIGN,
+    OP_SHR_ASSIGN,
+    OP_USHR,
+    OP_AND_ASSIGN,
+    OP_OR_ASSIGN,
+    OP_NULLISH_ASSIGN,
+    OP_ARROW,
+    OP_LOOSE_EQ,
+    OP_LOOSE_NOT_EQ,
+    OP_LT_EQ,
+    OP_GT_EQ,
+    OP_AND,
+    OP_OR,
+    OP_NULLISH,
+    OP_INCREMENT,
+    OP_DECREMENT,
+    OP_ADD_ASSIGN,
+    OP_SUB_ASSIGN,
+    OP_MUL_ASSIGN,
+    OP_DIV_ASSIGN,
+    OP_MOD_ASSIGN,
+    OP_BIT_AND_ASSIGN,
+    OP_BIT_OR_ASSIGN,
+    OP_BIT_XOR_ASSIGN,
+    OP_EXP,
+    OP_SHL,
+    OP_SHR,
+    OP_OPEN_BRACE,
+    OP_CLOSE_BRACE,
+    OP_OPEN_PAREN,
+    OP_CLOSE_PAREN,
+    OP_OPEN_BRACKET,
+    OP_CLOSE_BRACKET,
+    OP_SEMICOLON,
+    OP_COMMA,
+    OP_LT,
+    OP_GT,
+    OP_ADD,
+    OP_SUB,
+    OP_MUL,
+    OP_DIV,
+    OP_MOD,
+    OP_BIT_AND,
+    OP_BIT_OR,
+    OP_BIT_XOR,
+    OP_NOT,
+    OP_BIT_NOT,
+    OP_CONDITIONAL,
+    OP_COLON,
+    OP_EQ,
+    OP_DOT,
+];
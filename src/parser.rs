@@ -0,0 +1,2116 @@
+use std::collections::HashSet;
+use std::mem;
+use std::rc::Rc;
+use std::slice;
+
+use crate::ast::{
+    AssignmentOperator, BinaryOperator, DeclarationKind, Declarator, Expression,
+    FunctionDeclaration, LogicalOperator, Program, Property, PropertyKey, Statement,
+    TemplateElement, UnaryOperator, UpdateOperator,
+};
+use crate::error::{ErrorKind, JsError};
+use crate::interpreter_2::MAX_NESTING_DEPTH;
+use crate::keywords::{
+    KW_BREAK, KW_CONST, KW_CONTINUE, KW_DELETE, KW_DO, KW_ELSE, KW_FOR, KW_FUNCTION, KW_IF, KW_IN,
+    KW_INSTANCEOF, KW_LET, KW_RETURN, KW_TYPEOF, KW_VAR, KW_VOID, KW_WHILE,
+};
+use crate::lexer::{Lexer, Position, Token, TokenKind};
+use crate::operators::{
+    OP_ADD, OP_ADD_ASSIGN, OP_AND, OP_AND_ASSIGN, OP_BIT_AND, OP_BIT_AND_ASSIGN, OP_BIT_NOT,
+    OP_BIT_OR, OP_BIT_OR_ASSIGN, OP_BIT_XOR, OP_BIT_XOR_ASSIGN, OP_CONDITIONAL, OP_DECREMENT,
+    OP_DIV, OP_DIV_ASSIGN, OP_EQ, OP_EXP, OP_EXP_ASSIGN, OP_GT, OP_GT_EQ, OP_INCREMENT,
+    OP_LOOSE_EQ, OP_LOOSE_NOT_EQ, OP_LT, OP_LT_EQ, OP_MOD, OP_MOD_ASSIGN, OP_MUL, OP_MUL_ASSIGN,
+    OP_NOT, OP_NULLISH, OP_NULLISH_ASSIGN, OP_OR, OP_OR_ASSIGN, OP_SHL, OP_SHL_ASSIGN, OP_SHR,
+    OP_SHR_ASSIGN, OP_STRICT_EQ, OP_STRICT_NOT_EQ, OP_SUB, OP_SUB_ASSIGN, OP_USHR, OP_USHR_ASSIGN,
+};
+use crate::types::Num;
+
+// How tightly each kind of operator binds, from loosest to tightest.
+const ASSIGNMENT: u8 = 1;
+const CONDITIONAL: u8 = 2;
+const LOGICAL_OR: u8 = 3;
+const LOGICAL_AND: u8 = 4;
+const BITWISE_OR: u8 = 5;
+const BITWISE_XOR: u8 = 6;
+const BITWISE_AND: u8 = 7;
+const EQUALITY: u8 = 8;
+const RELATIONAL: u8 = 9;
+const SHIFT: u8 = 10;
+const ADDITIVE: u8 = 11;
+const MULTIPLICATIVE: u8 = 12;
+const EXPONENT: u8 = 13;
+
+/// The kinds of operator that can follow an operand.
+enum Infix {
+    Binary(BinaryOperator),
+    Logical(LogicalOperator),
+    Assign(AssignmentOperator),
+    Conditional,
+}
+
+/// Whether `expression` can be assigned to or updated with `++` and `--`.
+fn is_assignable(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Identifier(..) | Expression::Member(..) | Expression::Index(..)
+    )
+}
+
+fn update_operator(punctuator: &str) -> UpdateOperator {
+    match punctuator {
+        OP_INCREMENT => UpdateOperator::Increment,
+        _ => UpdateOperator::Decrement,
+    }
+}
+
+/// The error strict mode gives for a string literal's source `raw`, if it contains a legacy
+/// octal escape such as `\07` or one of the digit escapes `\8` and `\9`.
+fn legacy_escape(raw: &str) -> Option<&'static str> {
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            continue;
+        }
+        match chars.next() {
+            Some('0') if !chars.peek().is_some_and(|c| c.is_ascii_digit()) => (),
+            Some('0'..='7') => {
+                return Some("Octal escape sequences are not allowed in strict mode.")
+            }
+            Some('8' | '9') => return Some("\\8 and \\9 are not allowed in strict mode."),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Drops the comments and line terminators the parser doesn't look at.
+fn significant(tokens: Vec<Token>) -> Vec<Token> {
+    tokens
+        .into_iter()
+        .filter(|token| {
+            !matches!(
+                token.kind,
+                TokenKind::Comment(_) | TokenKind::LineTerminator
+            )
+        })
+        .collect()
+}
+
+/// The names declared directly in one block or function body, used to reject redeclarations.
+#[derive(Default)]
+struct Scope {
+    lexical: HashSet<String>,
+    var: HashSet<String>,
+    /// The functions declared directly in a block, which are lexical there but may share a
+    /// name with each other in sloppy mode.
+    functions: HashSet<String>,
+    function: bool,
+}
+
+/// The statements `break` and `continue` can target from the current position. Reset on
+/// entering a function, since jumps can't cross function boundaries.
+#[derive(Default)]
+struct Targets {
+    loops: usize,
+    /// Enclosing labels, and whether each one labels a loop.
+    labels: Vec<(String, bool)>,
+}
+
+pub struct Parser {
+    source: Rc<str>,
+    tokens: Vec<Token>,
+    current: usize,
+    scopes: Vec<Scope>,
+    targets: Targets,
+    /// Whether the code being parsed is strict mode code, i.e. it or an enclosing function
+    /// starts with a `"use strict"` directive.
+    strict: bool,
+    /// How deeply the expressions and statements being parsed are nested.
+    depth: usize,
+}
+
+impl Parser {
+    pub fn new(source: &str) -> Result<Self, JsError> {
+        let tokens = significant(Lexer::tokenize(source)?);
+        Ok(Self {
+            source: Rc::from(source),
+            tokens,
+            current: 0,
+            scopes: vec![Scope {
+                function: true,
+                ..Scope::default()
+            }],
+            targets: Targets::default(),
+            strict: false,
+            depth: 0,
+        })
+    }
+
+    /// Consumes the current token and returns it.
+    fn current(&mut self) -> Token {
+        let current = self.tokens[self.current].clone();
+        if current.kind != TokenKind::Eof {
+            self.current += 1;
+        }
+        current
+    }
+
+    /// Gets the next token but doesn't consume it.
+    fn lookahead(&self) -> &TokenKind {
+        &self.tokens[self.current].kind
+    }
+
+    fn unexpected(&self, token: &Token) -> JsError {
+        match &token.kind {
+            TokenKind::Eof => JsError::syntax("Unexpected end of input", token.position),
+            kind => JsError::syntax(format!("Unexpected token {}", kind), token.position),
+        }
+    }
+
+    fn match_punctuator(&mut self, punctuator: &str) -> Result<Token, JsError> {
+        let current = self.current();
+        match current.kind {
+            TokenKind::Punctuator(p) if p == punctuator => Ok(current),
+            _ => Err(self.unexpected(&current)),
+        }
+    }
+
+    fn matches_punctuator(&self, punctuator: &str) -> bool {
+        self.matches_any(&[punctuator])
+    }
+
+    fn matches_any(&self, punctuators: &[&str]) -> bool {
+        match self.lookahead() {
+            TokenKind::Punctuator(p) => punctuators.contains(p),
+            _ => false,
+        }
+    }
+
+    fn is_eof(&self) -> bool {
+        *self.lookahead() == TokenKind::Eof
+    }
+
+    fn ident(&mut self) -> Result<String, JsError> {
+        let current = self.current();
+        match current.kind {
+            TokenKind::Identifier(ident) => Ok(ident),
+            _ => Err(self.unexpected(&current)),
+        }
+    }
+
+    /// Counts one more level of nesting, so that code nested too deeply to parse or run without
+    /// overflowing the stack throws a `RangeError` instead.
+    fn deeper(&mut self) -> Result<(), JsError> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return Err(JsError::new(
+                ErrorKind::RangeError,
+                "Maximum call stack size exceeded",
+                Some(self.tokens[self.current].position),
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn redeclared(name: &str, position: Position) -> JsError {
+        JsError::syntax(
+            format!("Identifier '{}' has already been declared", name),
+            position,
+        )
+    }
+
+    /// Records a `let` or `const` in the current scope, which may not share its name with
+    /// anything else declared there.
+    fn declare_lexical(&mut self, name: &str, position: Position) -> Result<(), JsError> {
+        let scope = self.scopes.last_mut().unwrap();
+        if scope.lexical.contains(name) || scope.var.contains(name) {
+            return Err(Self::redeclared(name, position));
+        }
+        scope.lexical.insert(String::from(name));
+        Ok(())
+    }
+
+    /// Records a `var` or function in every scope up to the enclosing function, since it is
+    /// hoisted through all of them.
+    fn declare_var(&mut self, name: &str, position: Position) -> Result<(), JsError> {
+        for scope in self.scopes.iter_mut().rev() {
+            if scope.lexical.contains(name) {
+                return Err(Self::redeclared(name, position));
+            }
+            scope.var.insert(String::from(name));
+            if scope.function {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a function declaration. At the top of a function body or script it is hoisted
+    /// like a `var`, while in a block it is lexical to that block.
+    fn declare_function(&mut self, name: &str, position: Position) -> Result<(), JsError> {
+        let strict = self.strict;
+        let scope = self.scopes.last_mut().unwrap();
+        if scope.function {
+            return self.declare_var(name, position);
+        }
+        let duplicate_function = !strict && scope.functions.contains(name);
+        if (scope.lexical.contains(name) && !duplicate_function) || scope.var.contains(name) {
+            return Err(Self::redeclared(name, position));
+        }
+        scope.lexical.insert(String::from(name));
+        scope.functions.insert(String::from(name));
+        Ok(())
+    }
+
+    /// Whether the parser is inside a function body rather than at the top level.
+    fn in_function(&self) -> bool {
+        self.scopes.iter().filter(|scope| scope.function).count() > 1
+    }
+
+    pub fn program(&mut self) -> Result<Program, JsError> {
+        self.directives(self.current);
+        let mut body = Vec::new();
+        while !self.is_eof() {
+            body.push(self.statement()?);
+        }
+        if !self.strict {
+            annex_b(&mut body, &[]);
+        }
+        Ok(Program { body })
+    }
+
+    fn statement(&mut self) -> Result<Statement, JsError> {
+        if let TokenKind::Identifier(_) = self.lookahead() {
+            if self.tokens[self.current + 1].kind == TokenKind::Punctuator(":") {
+                return self.labeled();
+            }
+        }
+        let statement = match self.lookahead() {
+            TokenKind::Keyword(KW_VAR) => self.declaration(DeclarationKind::Var)?,
+            TokenKind::Keyword(KW_LET) => self.declaration(DeclarationKind::Let)?,
+            TokenKind::Keyword(KW_CONST) => self.declaration(DeclarationKind::Const)?,
+            TokenKind::Keyword(KW_FUNCTION) => return self.function(),
+            TokenKind::Keyword(KW_RETURN) => self.return_statement()?,
+            TokenKind::Keyword(KW_IF) => return self.if_statement(),
+            TokenKind::Keyword(KW_WHILE) => return self.while_statement(),
+            TokenKind::Keyword(KW_DO) => {
+                // A `;` is inserted after `do … while (…)` even on the same line.
+                let statement = self.do_while()?;
+                if self.matches_punctuator(";") {
+                    self.match_punctuator(";")?;
+                }
+                return Ok(statement);
+            }
+            TokenKind::Keyword(KW_FOR) => return self.for_statement(),
+            TokenKind::Keyword(KW_BREAK) => self.jump(KW_BREAK)?,
+            TokenKind::Keyword(KW_CONTINUE) => self.jump(KW_CONTINUE)?,
+            TokenKind::Punctuator("{") => return Ok(Statement::Block(self.block()?)),
+            TokenKind::Punctuator(";") => Statement::Empty,
+            _ => Statement::Expression(self.expression()?),
+        };
+        self.semicolon()?;
+        Ok(statement)
+    }
+
+    /// Consumes the `;` ending a statement. It may only be left out before a `}`, at the end
+    /// of the input or after a line break, where it is inserted automatically.
+    fn semicolon(&mut self) -> Result<(), JsError> {
+        if self.matches_punctuator(";") {
+            self.match_punctuator(";")?;
+            return Ok(());
+        }
+        if self.matches_punctuator("}") || self.is_eof() || self.newline_before() {
+            return Ok(());
+        }
+        Err(self.unexpected(&self.tokens[self.current]))
+    }
+
+    /// Whether a line break separates the current token from the one before it, including one
+    /// inside a comment between them.
+    fn newline_before(&self) -> bool {
+        self.newline_before_token(self.current)
+    }
+
+    /// Whether a line break separates the token at `index` from the one before it.
+    fn newline_before_token(&self, index: usize) -> bool {
+        if index == 0 {
+            return false;
+        }
+        let previous = &self.tokens[index - 1];
+        let current = &self.tokens[index];
+        self.source
+            .get(previous.end..current.start)
+            .is_some_and(|between| between.contains(['\n', '\r', '\u{2028}', '\u{2029}']))
+    }
+
+    /// Scans the directive prologue, the string literal statements at the start of a script or
+    /// function body, and switches to strict mode if one of them is `"use strict"`. Only an
+    /// exact match counts, so an escaped or parenthesized string doesn't.
+    fn directives(&mut self, mut index: usize) {
+        while let TokenKind::String(_) = self.tokens[index].kind {
+            let token = &self.tokens[index];
+            let next = &self.tokens[index + 1];
+            match next.kind {
+                TokenKind::Punctuator(";" | "}") | TokenKind::Eof => (),
+                _ if self.newline_before_token(index + 1) => (),
+                _ => return,
+            }
+            if &self.source[token.start + 1..token.end - 1] == "use strict" {
+                self.strict = true;
+            }
+            index += 1;
+            if next.kind == TokenKind::Punctuator(";") {
+                index += 1;
+            }
+        }
+    }
+
+    fn declaration(&mut self, kind: DeclarationKind) -> Result<Statement, JsError> {
+        self.current();
+        let mut declarators = Vec::new();
+        loop {
+            let position = self.tokens[self.current].position;
+            let name = self.ident()?;
+            match kind {
+                DeclarationKind::Var => self.declare_var(&name, position)?,
+                _ => self.declare_lexical(&name, position)?,
+            }
+            let init = if self.matches_punctuator(OP_EQ) {
+                self.match_punctuator(OP_EQ)?;
+                Some(self.assignment()?)
+            } else if kind == DeclarationKind::Const {
+                return Err(JsError::syntax(
+                    "Missing initializer in const declaration",
+                    position,
+                ));
+            } else {
+                None
+            };
+            declarators.push(Declarator {
+                name,
+                init,
+                position,
+            });
+            if !self.matches_punctuator(",") {
+                break;
+            }
+            self.match_punctuator(",")?;
+        }
+        Ok(Statement::Declaration(kind, declarators))
+    }
+
+    /// Parses the body of an `if` or loop, which can't be a bare `let` or `const`.
+    fn substatement(&mut self) -> Result<Statement, JsError> {
+        let position = self.tokens[self.current].position;
+        match self.lookahead() {
+            TokenKind::Keyword(KW_LET | KW_CONST) => Err(JsError::syntax(
+                "Lexical declaration cannot appear in a single-statement context",
+                position,
+            )),
+            TokenKind::Keyword(KW_FUNCTION) if self.strict => Err(JsError::syntax(
+                "In strict mode code, functions can only be declared at top level or inside a \
+                block.",
+                position,
+            )),
+            TokenKind::Keyword(KW_FUNCTION) => Err(JsError::syntax(
+                "In non-strict mode code, functions can only be declared at top level, inside a \
+                block, or as the body of an if statement.",
+                position,
+            )),
+            _ => {
+                self.deeper()?;
+                let statement = self.statement();
+                self.depth -= 1;
+                statement
+            }
+        }
+    }
+
+    /// Parses the body of an `if` or `else`. Sloppy mode allows a function declaration there,
+    /// which acts as if it were in a block of its own.
+    fn if_body(&mut self) -> Result<Statement, JsError> {
+        if self.strict || *self.lookahead() != TokenKind::Keyword(KW_FUNCTION) {
+            return self.substatement();
+        }
+        self.scopes.push(Scope::default());
+        let function = self.function();
+        self.scopes.pop();
+        Ok(Statement::Block(vec![function?]))
+    }
+
+    fn if_statement(&mut self) -> Result<Statement, JsError> {
+        self.current();
+        let test = self.condition()?;
+        let consequent = self.if_body()?;
+        let alternate = match self.lookahead() {
+            TokenKind::Keyword(KW_ELSE) => {
+                self.current();
+                Some(Box::new(self.if_body()?))
+            }
+            _ => None,
+        };
+        Ok(Statement::If(test, Box::new(consequent), alternate))
+    }
+
+    fn labeled(&mut self) -> Result<Statement, JsError> {
+        let position = self.tokens[self.current].position;
+        let label = self.ident()?;
+        self.match_punctuator(":")?;
+        if self.targets.labels.iter().any(|(name, _)| *name == label) {
+            return Err(JsError::syntax(
+                format!("Label '{}' has already been declared", label),
+                position,
+            ));
+        }
+        let is_loop = matches!(
+            self.lookahead(),
+            TokenKind::Keyword(KW_WHILE | KW_DO | KW_FOR)
+        );
+        self.targets.labels.push((label.clone(), is_loop));
+        let body = self.substatement()?;
+        self.targets.labels.pop();
+        Ok(Statement::Labeled(label, Box::new(body)))
+    }
+
+    /// Parses the body of a loop, where `break` and `continue` without a label are allowed.
+    fn loop_body(&mut self) -> Result<Statement, JsError> {
+        self.targets.loops += 1;
+        let body = self.substatement()?;
+        self.targets.loops -= 1;
+        Ok(body)
+    }
+
+    /// Parses a parenthesized loop or `if` condition.
+    fn condition(&mut self) -> Result<Expression, JsError> {
+        self.match_punctuator("(")?;
+        let test = self.expression()?;
+        self.match_punctuator(")")?;
+        Ok(test)
+    }
+
+    fn while_statement(&mut self) -> Result<Statement, JsError> {
+        self.current();
+        let test = self.condition()?;
+        Ok(Statement::While(test, Box::new(self.loop_body()?)))
+    }
+
+    fn do_while(&mut self) -> Result<Statement, JsError> {
+        self.current();
+        let body = self.loop_body()?;
+        let current = self.current();
+        if current.kind != TokenKind::Keyword(KW_WHILE) {
+            return Err(self.unexpected(&current));
+        }
+        Ok(Statement::DoWhile(Box::new(body), self.condition()?))
+    }
+
+    fn for_statement(&mut self) -> Result<Statement, JsError> {
+        self.current();
+        self.match_punctuator("(")?;
+        // Bindings declared in the head belong to the loop rather than the enclosing block.
+        self.scopes.push(Scope::default());
+        let init = match self.lookahead() {
+            TokenKind::Punctuator(";") => None,
+            TokenKind::Keyword(KW_VAR) => Some(self.declaration(DeclarationKind::Var)?),
+            TokenKind::Keyword(KW_LET) => Some(self.declaration(DeclarationKind::Let)?),
+            TokenKind::Keyword(KW_CONST) => Some(self.declaration(DeclarationKind::Const)?),
+            _ => Some(Statement::Expression(self.expression()?)),
+        };
+        self.match_punctuator(";")?;
+        let test = match self.matches_punctuator(";") {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        self.match_punctuator(";")?;
+        let update = match self.matches_punctuator(")") {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        self.match_punctuator(")")?;
+        let body = self.loop_body()?;
+        self.scopes.pop();
+        Ok(Statement::For(
+            init.map(Box::new),
+            test,
+            update,
+            Box::new(body),
+        ))
+    }
+
+    /// Parses `break` or `continue` with an optional label on the same line.
+    fn jump(&mut self, keyword: &str) -> Result<Statement, JsError> {
+        let current = self.current();
+        let label = match &self.tokens[self.current].kind {
+            TokenKind::Identifier(label) if !self.newline_before() => {
+                let label = label.clone();
+                self.current();
+                Some(label)
+            }
+            _ => None,
+        };
+        let error = |message: String| Err(JsError::syntax(message, current.position));
+        match (&label, keyword) {
+            (None, KW_BREAK) if self.targets.loops == 0 => {
+                return error(String::from("Illegal break statement"))
+            }
+            (None, _) if self.targets.loops == 0 => {
+                return error(String::from(
+                    "Illegal continue statement: no surrounding iteration statement",
+                ))
+            }
+            (None, _) => (),
+            (Some(label), _) => match self.targets.labels.iter().find(|(name, _)| name == label) {
+                None => return error(format!("Undefined label '{}'", label)),
+                Some((_, false)) if keyword == KW_CONTINUE => {
+                    return error(format!(
+                        "Illegal continue statement: '{}' does not denote an iteration statement",
+                        label
+                    ))
+                }
+                Some(_) => (),
+            },
+        }
+        Ok(match keyword {
+            KW_BREAK => Statement::Break(label),
+            _ => Statement::Continue(label),
+        })
+    }
+
+    fn return_statement(&mut self) -> Result<Statement, JsError> {
+        let keyword = self.current();
+        if !self.in_function() {
+            return Err(JsError::syntax(
+                "Illegal return statement",
+                keyword.position,
+            ));
+        }
+        // A line break after `return` ends the statement.
+        if self.matches_any(&[";", "}"]) || self.is_eof() || self.newline_before() {
+            return Ok(Statement::Return(None));
+        }
+        Ok(Statement::Return(Some(self.expression()?)))
+    }
+
+    fn function(&mut self) -> Result<Statement, JsError> {
+        let keyword = self.current();
+        let position = self.tokens[self.current].position;
+        let name = self.ident()?;
+        self.declare_function(&name, position)?;
+        Ok(Statement::Function(self.function_rest(
+            name,
+            keyword.start,
+            position,
+        )?))
+    }
+
+    /// Parses the parameters and body that follow a function's name. The function's source
+    /// text runs from `start` to the end of its body.
+    fn function_rest(
+        &mut self,
+        name: String,
+        start: usize,
+        position: Position,
+    ) -> Result<FunctionDeclaration, JsError> {
+        self.function_scope(|this| {
+            let params = this.params()?;
+            let mut body = this.function_body()?;
+            if !this.strict {
+                annex_b(&mut body, &params);
+            }
+            Ok(FunctionDeclaration {
+                name,
+                params: params.into(),
+                body: body.into(),
+                source: this.source_from(start),
+                position,
+                annex_b: false,
+            })
+        })
+    }
+
+    /// The source text from `start` to the end of the last token consumed.
+    fn source_from(&self, start: usize) -> Rc<str> {
+        Rc::from(&self.source[start..self.tokens[self.current - 1].end])
+    }
+
+    /// Runs `parse` in a new function scope with no enclosing loops or labels.
+    fn function_scope<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, JsError>,
+    ) -> Result<T, JsError> {
+        self.scopes.push(Scope {
+            function: true,
+            ..Scope::default()
+        });
+        let targets = mem::take(&mut self.targets);
+        let strict = self.strict;
+        let result = parse(self);
+        self.targets = targets;
+        self.strict = strict;
+        self.scopes.pop();
+        result
+    }
+
+    /// Whether the tokens from the current one start an arrow function, i.e. they are an
+    /// identifier or a parenthesized list followed by `=>`.
+    fn is_arrow(&self) -> bool {
+        let arrow = |index: usize| {
+            self.tokens
+                .get(index)
+                .is_some_and(|token| token.kind == TokenKind::Punctuator("=>"))
+        };
+        match self.lookahead() {
+            TokenKind::Identifier(_) => arrow(self.current + 1),
+            // Parameters are plain identifiers, so stopping at anything else means nested
+            // parentheses don't each scan to the end of the script.
+            TokenKind::Punctuator("(") => {
+                for (index, token) in self.tokens.iter().enumerate().skip(self.current + 1) {
+                    match token.kind {
+                        TokenKind::Identifier(_) | TokenKind::Punctuator(",") => (),
+                        TokenKind::Punctuator(")") => return arrow(index + 1),
+                        _ => return false,
+                    }
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn arrow(&mut self) -> Result<Expression, JsError> {
+        let Token {
+            start, position, ..
+        } = self.tokens[self.current];
+        self.function_scope(|this| {
+            let params = if this.matches_punctuator("(") {
+                this.params()?
+            } else {
+                let position = this.tokens[this.current].position;
+                let param = this.ident()?;
+                this.declare_var(&param, position)?;
+                vec![param]
+            };
+            this.match_punctuator("=>")?;
+            let body = if this.matches_punctuator("{") {
+                let mut body = this.function_body()?;
+                if !this.strict {
+                    annex_b(&mut body, &params);
+                }
+                body
+            } else {
+                vec![Statement::Return(Some(this.assignment()?))]
+            };
+            Ok(Expression::Arrow(FunctionDeclaration {
+                name: String::new(),
+                params: params.into(),
+                body: body.into(),
+                source: this.source_from(start),
+                position,
+                annex_b: false,
+            }))
+        })
+    }
+
+    fn params(&mut self) -> Result<Vec<String>, JsError> {
+        self.match_punctuator("(")?;
+        let mut params = Vec::new();
+        while !self.matches_punctuator(")") {
+            let position = self.tokens[self.current].position;
+            let param = self.ident()?;
+            self.declare_var(&param, position)?;
+            params.push(param);
+            if !self.matches_punctuator(",") {
+                break;
+            }
+            self.match_punctuator(",")?;
+        }
+        self.match_punctuator(")")?;
+        Ok(params)
+    }
+
+    fn block(&mut self) -> Result<Vec<Statement>, JsError> {
+        self.scopes.push(Scope::default());
+        let body = self.body()?;
+        self.scopes.pop();
+        Ok(body)
+    }
+
+    /// Parses a function's body, which may start with directives such as `"use strict"`.
+    fn function_body(&mut self) -> Result<Vec<Statement>, JsError> {
+        if self.matches_punctuator("{") {
+            self.directives(self.current + 1);
+        }
+        self.body()
+    }
+
+    /// Parses the statements between a pair of braces in the current scope.
+    fn body(&mut self) -> Result<Vec<Statement>, JsError> {
+        self.deeper()?;
+        self.match_punctuator("{")?;
+        let mut body = Vec::new();
+        while !self.matches_punctuator("}") {
+            if self.is_eof() {
+                return Err(self.unexpected(&self.tokens[self.current]));
+            }
+            body.push(self.statement()?);
+        }
+        self.match_punctuator("}")?;
+        self.depth -= 1;
+        Ok(body)
+    }
+
+    /// Parses a full expression, including the comma operator.
+    pub fn expression(&mut self) -> Result<Expression, JsError> {
+        let first = self.assignment()?;
+        if !self.matches_punctuator(",") {
+            return Ok(first);
+        }
+        let mut expressions = vec![first];
+        while self.matches_punctuator(",") {
+            self.match_punctuator(",")?;
+            expressions.push(self.assignment()?);
+        }
+        Ok(Expression::Sequence(expressions))
+    }
+
+    /// Parses an expression that stops at a comma, as used for arguments and initializers.
+    fn assignment(&mut self) -> Result<Expression, JsError> {
+        self.binary(ASSIGNMENT)
+    }
+
+    /// The infix operator at the current token and how tightly it binds, if there is one.
+    fn infix(&self) -> Option<(Infix, u8)> {
+        use BinaryOperator::*;
+        let (infix, precedence) = match self.lookahead() {
+            TokenKind::Punctuator(punctuator) => match *punctuator {
+                OP_EQ => (Infix::Assign(AssignmentOperator::Assign), ASSIGNMENT),
+                OP_ADD_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(Add)), ASSIGNMENT),
+                OP_SUB_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(Sub)), ASSIGNMENT),
+                OP_MUL_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(Mul)), ASSIGNMENT),
+                OP_DIV_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(Div)), ASSIGNMENT),
+                OP_MOD_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(Mod)), ASSIGNMENT),
+                OP_EXP_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(Exp)), ASSIGNMENT),
+                OP_SHL_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(Shl)), ASSIGNMENT),
+                OP_SHR_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(Shr)), ASSIGNMENT),
+                OP_USHR_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(UShr)), ASSIGNMENT),
+                OP_BIT_AND_ASSIGN => (
+                    Infix::Assign(AssignmentOperator::Binary(BitAnd)),
+                    ASSIGNMENT,
+                ),
+                OP_BIT_OR_ASSIGN => (Infix::Assign(AssignmentOperator::Binary(BitOr)), ASSIGNMENT),
+                OP_BIT_XOR_ASSIGN => (
+                    Infix::Assign(AssignmentOperator::Binary(BitXor)),
+                    ASSIGNMENT,
+                ),
+                OP_AND_ASSIGN => (
+                    Infix::Assign(AssignmentOperator::Logical(LogicalOperator::And)),
+                    ASSIGNMENT,
+                ),
+                OP_OR_ASSIGN => (
+                    Infix::Assign(AssignmentOperator::Logical(LogicalOperator::Or)),
+                    ASSIGNMENT,
+                ),
+                OP_NULLISH_ASSIGN => (
+                    Infix::Assign(AssignmentOperator::Logical(LogicalOperator::Nullish)),
+                    ASSIGNMENT,
+                ),
+                OP_CONDITIONAL => (Infix::Conditional, CONDITIONAL),
+                OP_OR => (Infix::Logical(LogicalOperator::Or), LOGICAL_OR),
+                OP_NULLISH => (Infix::Logical(LogicalOperator::Nullish), LOGICAL_OR),
+                OP_AND => (Infix::Logical(LogicalOperator::And), LOGICAL_AND),
+                OP_BIT_OR => (Infix::Binary(BitOr), BITWISE_OR),
+                OP_BIT_XOR => (Infix::Binary(BitXor), BITWISE_XOR),
+                OP_BIT_AND => (Infix::Binary(BitAnd), BITWISE_AND),
+                OP_LOOSE_EQ => (Infix::Binary(LooseEq), EQUALITY),
+                OP_LOOSE_NOT_EQ => (Infix::Binary(LooseNotEq), EQUALITY),
+                OP_STRICT_EQ => (Infix::Binary(StrictEq), EQUALITY),
+                OP_STRICT_NOT_EQ => (Infix::Binary(StrictNotEq), EQUALITY),
+                OP_LT => (Infix::Binary(Lt), RELATIONAL),
+                OP_GT => (Infix::Binary(Gt), RELATIONAL),
+                OP_LT_EQ => (Infix::Binary(LtEq), RELATIONAL),
+                OP_GT_EQ => (Infix::Binary(GtEq), RELATIONAL),
+                OP_SHL => (Infix::Binary(Shl), SHIFT),
+                OP_SHR => (Infix::Binary(Shr), SHIFT),
+                OP_USHR => (Infix::Binary(UShr), SHIFT),
+                OP_ADD => (Infix::Binary(Add), ADDITIVE),
+                OP_SUB => (Infix::Binary(Sub), ADDITIVE),
+                OP_MUL => (Infix::Binary(Mul), MULTIPLICATIVE),
+                OP_DIV => (Infix::Binary(Div), MULTIPLICATIVE),
+                OP_MOD => (Infix::Binary(Mod), MULTIPLICATIVE),
+                OP_EXP => (Infix::Binary(Exp), EXPONENT),
+                _ => return None,
+            },
+            TokenKind::Keyword(KW_IN) => (Infix::Binary(In), RELATIONAL),
+            TokenKind::Keyword(KW_INSTANCEOF) => (Infix::Binary(InstanceOf), RELATIONAL),
+            _ => return None,
+        };
+        Some((infix, precedence))
+    }
+
+    /// Parses operators that bind at least as tightly as `min_precedence`, by precedence
+    /// climbing. Assignment, the conditional operator and `**` associate to the right and
+    /// everything else to the left.
+    fn binary(&mut self, min_precedence: u8) -> Result<Expression, JsError> {
+        // Each operator nests the expression so far one level deeper.
+        let depth = self.depth;
+        self.deeper()?;
+        let start = self.tokens[self.current].position;
+        // `-2 ** 2` is ambiguous, so a unary operand of `**` must be parenthesized.
+        let mut unary = match self.lookahead() {
+            TokenKind::Punctuator(p) => [OP_ADD, OP_SUB, OP_NOT, OP_BIT_NOT].contains(p),
+            TokenKind::Keyword(k) => [KW_TYPEOF, KW_VOID, KW_DELETE].contains(k),
+            _ => false,
+        };
+        // `??` can't be mixed with `&&` or `||` unless one of them is parenthesized.
+        let mut grouped = self.matches_punctuator("(");
+        let mut left = self.unary()?;
+        while let Some((infix, precedence)) = self.infix() {
+            if precedence < min_precedence {
+                break;
+            }
+            self.deeper()?;
+            let operator = self.current();
+            left = match infix {
+                Infix::Binary(BinaryOperator::Exp) if unary => {
+                    return Err(JsError::syntax(
+                        "Unary operator used immediately before exponentiation expression. \
+                        Parenthesis must be used to disambiguate operator precedence",
+                        operator.position,
+                    ))
+                }
+                Infix::Binary(BinaryOperator::Exp) => Expression::Binary(
+                    BinaryOperator::Exp,
+                    Box::new(left),
+                    Box::new(self.binary(EXPONENT)?),
+                    operator.position,
+                ),
+                Infix::Binary(binary) => Expression::Binary(
+                    binary,
+                    Box::new(left),
+                    Box::new(self.binary(precedence + 1)?),
+                    operator.position,
+                ),
+                Infix::Logical(logical) => {
+                    let nullish = logical == LogicalOperator::Nullish;
+                    if let Expression::Logical(inner, ..) = &left {
+                        if !grouped && nullish != (*inner == LogicalOperator::Nullish) {
+                            return Err(self.unexpected(&operator));
+                        }
+                    }
+                    let right = match nullish {
+                        true => self.binary(BITWISE_OR)?,
+                        false => self.binary(precedence + 1)?,
+                    };
+                    Expression::Logical(logical, Box::new(left), Box::new(right))
+                }
+                Infix::Conditional => {
+                    let consequent = self.assignment()?;
+                    self.match_punctuator(":")?;
+                    Expression::Conditional(
+                        Box::new(left),
+                        Box::new(consequent),
+                        Box::new(self.assignment()?),
+                    )
+                }
+                Infix::Assign(assignment) => {
+                    if !is_assignable(&left) {
+                        return Err(JsError::syntax(
+                            "Invalid left-hand side in assignment",
+                            start,
+                        ));
+                    }
+                    Expression::Assign(
+                        assignment,
+                        Box::new(left),
+                        Box::new(self.assignment()?),
+                        start,
+                    )
+                }
+            };
+            unary = false;
+            grouped = false;
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    /// Parses prefix operators, then postfix `++` and `--`.
+    fn unary(&mut self) -> Result<Expression, JsError> {
+        let current = self.tokens[self.current].clone();
+        let operator = match current.kind {
+            TokenKind::Punctuator(OP_SUB) => UnaryOperator::Minus,
+            TokenKind::Punctuator(OP_ADD) => UnaryOperator::Plus,
+            TokenKind::Punctuator(OP_NOT) => UnaryOperator::Not,
+            TokenKind::Punctuator(OP_BIT_NOT) => UnaryOperator::BitNot,
+            TokenKind::Keyword(KW_TYPEOF) => UnaryOperator::Typeof,
+            TokenKind::Keyword(KW_VOID) => UnaryOperator::Void,
+            TokenKind::Keyword(KW_DELETE) => UnaryOperator::Delete,
+            TokenKind::Punctuator(update @ (OP_INCREMENT | OP_DECREMENT)) => {
+                self.current();
+                self.deeper()?;
+                let operand = self.unary()?;
+                self.depth -= 1;
+                if !is_assignable(&operand) {
+                    return Err(JsError::syntax(
+                        "Invalid left-hand side expression in prefix operation",
+                        self.tokens[self.current - 1].position,
+                    ));
+                }
+                return Ok(Expression::Update(
+                    update_operator(update),
+                    true,
+                    Box::new(operand),
+                    current.position,
+                ));
+            }
+            _ => return self.postfix(),
+        };
+        self.current();
+        self.deeper()?;
+        let operand = self.unary()?;
+        self.depth -= 1;
+        Ok(Expression::Unary(
+            operator,
+            Box::new(operand),
+            current.position,
+        ))
+    }
+
+    fn postfix(&mut self) -> Result<Expression, JsError> {
+        let start = self.tokens[self.current].position;
+        let operand = self.call()?;
+        let current = &self.tokens[self.current];
+        // A line break before `++` or `--` ends the statement instead.
+        match current.kind {
+            TokenKind::Punctuator(update @ (OP_INCREMENT | OP_DECREMENT))
+                if !self.newline_before() =>
+            {
+                if !is_assignable(&operand) {
+                    return Err(JsError::syntax(
+                        "Invalid left-hand side expression in postfix operation",
+                        start,
+                    ));
+                }
+                self.current();
+                Ok(Expression::Update(
+                    update_operator(update),
+                    false,
+                    Box::new(operand),
+                    start,
+                ))
+            }
+            _ => Ok(operand),
+        }
+    }
+
+    fn call(&mut self) -> Result<Expression, JsError> {
+        let depth = self.depth;
+        let mut prev = self.term()?;
+        loop {
+            self.deeper()?;
+            let position = self.tokens[self.current].position;
+            if self.matches_punctuator(".") {
+                self.match_punctuator(".")?;
+                let current = self.current();
+                let property = match current.kind {
+                    TokenKind::Identifier(name) => name,
+                    TokenKind::Keyword(keyword) => String::from(keyword),
+                    _ => return Err(self.unexpected(&current)),
+                };
+                prev = Expression::Member(Box::new(prev), property, position);
+            } else if self.matches_punctuator("[") {
+                self.match_punctuator("[")?;
+                let property = self.expression()?;
+                self.match_punctuator("]")?;
+                prev = Expression::Index(Box::new(prev), Box::new(property), position);
+            } else if self.matches_punctuator("(") {
+                prev = Expression::Call(Box::new(prev), self.arguments()?, position);
+            } else if let TokenKind::Template(_) = self.lookahead() {
+                let current = self.current();
+                let (strings, substitutions) = self.template(&current, true)?;
+                prev = Expression::TaggedTemplate(Box::new(prev), strings, substitutions, position);
+            } else {
+                self.depth = depth;
+                return Ok(prev);
+            }
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Expression>, JsError> {
+        self.match_punctuator("(")?;
+        let mut arguments = Vec::new();
+        while !self.matches_punctuator(")") {
+            arguments.push(self.assignment()?);
+            if !self.matches_punctuator(",") {
+                break;
+            }
+            self.match_punctuator(",")?;
+        }
+        self.match_punctuator(")")?;
+        Ok(arguments)
+    }
+
+    /// Parses the properties of an object literal after its opening brace.
+    fn object(&mut self) -> Result<Expression, JsError> {
+        let mut properties = Vec::new();
+        while !self.matches_punctuator("}") {
+            properties.push(self.property()?);
+            if !self.matches_punctuator(",") {
+                break;
+            }
+            self.match_punctuator(",")?;
+        }
+        self.match_punctuator("}")?;
+        Ok(Expression::Object(properties))
+    }
+
+    fn property(&mut self) -> Result<Property, JsError> {
+        if self.matches_punctuator("...") {
+            self.match_punctuator("...")?;
+            return Ok(Property::Spread(self.assignment()?));
+        }
+        let current = self.current();
+        let (start, position) = (current.start, current.position);
+        let key = match current.kind {
+            TokenKind::Identifier(name) if self.matches_any(&[",", "}"]) => {
+                let value = Expression::Identifier(name.clone(), current.position);
+                return Ok(Property::Init(PropertyKey::Named(name), value));
+            }
+            TokenKind::Identifier(name) => PropertyKey::Named(name),
+            TokenKind::Keyword(keyword) => PropertyKey::Named(String::from(keyword)),
+            TokenKind::Number(_) | TokenKind::String(_) => match self.literal(current)? {
+                Expression::Number(number) => PropertyKey::Named(Num::F64(number).to_string()),
+                Expression::String(string) => PropertyKey::Named(string),
+                _ => unreachable!("literal only returns numbers and strings"),
+            },
+            TokenKind::Punctuator("[") => {
+                let key = self.assignment()?;
+                self.match_punctuator("]")?;
+                PropertyKey::Computed(key, position)
+            }
+            _ => return Err(self.unexpected(&current)),
+        };
+        let value = if self.matches_punctuator("(") {
+            Expression::Function(self.function_rest(String::new(), start, position)?)
+        } else {
+            self.match_punctuator(":")?;
+            self.assignment()?
+        };
+        Ok(Property::Init(key, value))
+    }
+
+    /// Converts a number or string token to an expression, rejecting the legacy octal forms
+    /// strict mode forbids.
+    fn literal(&self, token: Token) -> Result<Expression, JsError> {
+        let raw = &self.source[token.start..token.end];
+        match token.kind {
+            TokenKind::Number(number) => {
+                if self.strict && raw.len() > 1 && raw.starts_with('0') {
+                    if raw.chars().all(|c| c.is_digit(8)) {
+                        return Err(JsError::syntax(
+                            "Octal literals are not allowed in strict mode.",
+                            token.position,
+                        ));
+                    }
+                    if raw[1..].starts_with(|c: char| c.is_ascii_digit()) {
+                        return Err(JsError::syntax(
+                            "Decimals with leading zeros are not allowed in strict mode.",
+                            token.position,
+                        ));
+                    }
+                }
+                Ok(Expression::Number(number))
+            }
+            TokenKind::String(string) => match legacy_escape(raw) {
+                Some(message) if self.strict => Err(JsError::syntax(message, token.position)),
+                _ => Ok(Expression::String(string)),
+            },
+            _ => Err(self.unexpected(&token)),
+        }
+    }
+
+    /// Splits the template literal `token` into its strings and substitutions. Only a tagged
+    /// template may contain invalid escapes.
+    fn template(
+        &mut self,
+        token: &Token,
+        tagged: bool,
+    ) -> Result<(Vec<TemplateElement>, Vec<Expression>), JsError> {
+        let source = Rc::clone(&self.source);
+        let position = Position {
+            column: token.position.column + 1,
+            ..token.position
+        };
+        let mut lexer = Lexer::resume(&source, token.start + 1, position);
+        let error = |message| JsError::syntax(message, token.position);
+        let mut strings = Vec::new();
+        let mut substitutions = Vec::new();
+        loop {
+            let string = lexer.template_string().map_err(error)?;
+            let cooked = match string.cooked {
+                Ok(cooked) => Some(cooked),
+                Err(_) if tagged => None,
+                Err(message) => return Err(error(message)),
+            };
+            strings.push(TemplateElement {
+                cooked,
+                raw: string.raw,
+            });
+            if !string.substitution {
+                return Ok((strings, substitutions));
+            }
+            // Parse the substitution's tokens in place of the rest of the script, so it shares
+            // the surrounding scope and strictness.
+            let mut tokens = significant(lexer.substitution().map_err(error)?);
+            let end = tokens[tokens.len() - 1].clone();
+            tokens.push(Token {
+                kind: TokenKind::Eof,
+                ..end
+            });
+            let tokens = mem::replace(&mut self.tokens, tokens);
+            let current = mem::replace(&mut self.current, 0);
+            let substitution = self.expression().and_then(|expression| {
+                self.match_punctuator("}")?;
+                Ok(expression)
+            });
+            self.tokens = tokens;
+            self.current = current;
+            substitutions.push(substitution?);
+        }
+    }
+
+    fn term(&mut self) -> Result<Expression, JsError> {
+        if self.is_arrow() {
+            return self.arrow();
+        }
+        let current = self.current();
+        match current.kind {
+            TokenKind::Number(_) | TokenKind::String(_) => self.literal(current),
+            TokenKind::Template(_) => {
+                let (strings, substitutions) = self.template(&current, false)?;
+                Ok(Expression::Template(
+                    strings,
+                    substitutions,
+                    current.position,
+                ))
+            }
+            TokenKind::Identifier(ident) => Ok(Expression::Identifier(ident, current.position)),
+            TokenKind::Keyword("true") => Ok(Expression::Boolean(true)),
+            TokenKind::Keyword("false") => Ok(Expression::Boolean(false)),
+            TokenKind::Keyword("null") => Ok(Expression::Null),
+            TokenKind::Keyword("this") => Ok(Expression::This),
+            TokenKind::Keyword(KW_FUNCTION) => {
+                let name = match self.lookahead() {
+                    TokenKind::Identifier(_) => self.ident()?,
+                    _ => String::new(),
+                };
+                Ok(Expression::Function(self.function_rest(
+                    name,
+                    current.start,
+                    current.position,
+                )?))
+            }
+            TokenKind::Punctuator("(") => {
+                let expression = self.expression()?;
+                self.match_punctuator(")")?;
+                Ok(expression)
+            }
+            TokenKind::Punctuator("{") => self.object(),
+            TokenKind::Punctuator("[") => {
+                let mut elements = Vec::new();
+                while !self.matches_punctuator("]") {
+                    elements.push(self.assignment()?);
+                    if !self.matches_punctuator(",") {
+                        break;
+                    }
+                    self.match_punctuator(",")?;
+                }
+                self.match_punctuator("]")?;
+                Ok(Expression::Array(elements))
+            }
+            _ => Err(self.unexpected(&current)),
+        }
+    }
+}
+
+/// Marks the functions declared in blocks within `body` that sloppy mode also hoists to a `var`
+/// in the enclosing function: those whose name isn't a parameter or already declared as `let`,
+/// `const` or a block function in a scope around them, where a `var` would clash.
+fn annex_b(body: &mut [Statement], params: &[String]) {
+    let mut scopes = vec![lexical_names(body, false)];
+    scopes[0].extend(params.iter().cloned());
+    for statement in body {
+        mark_annex_b(statement, &mut scopes);
+    }
+}
+
+/// The names `body` declares with `let` or `const`, and with function declarations if it's a
+/// block.
+fn lexical_names(body: &[Statement], block: bool) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for statement in body {
+        match statement {
+            Statement::Declaration(DeclarationKind::Let | DeclarationKind::Const, declarators) => {
+                names.extend(declarators.iter().map(|declarator| declarator.name.clone()));
+            }
+            Statement::Function(function) if block => {
+                names.insert(function.name.clone());
+            }
+            _ => (),
+        }
+    }
+    names
+}
+
+/// Marks the block functions in `statement` for `annex_b`, given the lexical names of the
+/// scopes around it.
+fn mark_annex_b(statement: &mut Statement, scopes: &mut Vec<HashSet<String>>) {
+    match statement {
+        Statement::Block(body) => {
+            scopes.push(lexical_names(body, true));
+            for statement in body {
+                match statement {
+                    Statement::Function(function) => {
+                        let enclosing = &scopes[..scopes.len() - 1];
+                        function.annex_b =
+                            !enclosing.iter().any(|names| names.contains(&function.name));
+                    }
+                    statement => mark_annex_b(statement, scopes),
+                }
+            }
+            scopes.pop();
+        }
+        Statement::If(_, consequent, alternate) => {
+            mark_annex_b(consequent, scopes);
+            if let Some(alternate) = alternate {
+                mark_annex_b(alternate, scopes);
+            }
+        }
+        Statement::While(_, body) | Statement::DoWhile(body, _) | Statement::Labeled(_, body) => {
+            mark_annex_b(body, scopes)
+        }
+        Statement::For(init, _, _, body) => {
+            scopes.push(match init {
+                Some(init) => lexical_names(slice::from_ref(&**init), false),
+                None => HashSet::new(),
+            });
+            mark_annex_b(body, scopes);
+            scopes.pop();
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::lexer::Position;
+
+    fn number(value: f64) -> Box<Expression> {
+        Box::new(Expression::Number(value))
+    }
+
+    fn at(column: usize) -> Position {
+        Position { line: 1, column }
+    }
+
+    fn identifier(name: &str, column: usize) -> Box<Expression> {
+        Box::new(Expression::Identifier(String::from(name), at(column)))
+    }
+
+    #[test]
+    fn parse_declarations() {
+        let program = Parser::new("var a = 1, b; const c = a;")
+            .unwrap()
+            .program()
+            .unwrap();
+        assert_eq!(
+            program.body,
+            vec![
+                Statement::Declaration(
+                    DeclarationKind::Var,
+                    vec![
+                        Declarator {
+                            name: String::from("a"),
+                            init: Some(Expression::Number(1f64)),
+                            position: at(5),
+                        },
+                        Declarator {
+                            name: String::from("b"),
+                            init: None,
+                            position: at(12),
+                        },
+                    ]
+                ),
+                Statement::Declaration(
+                    DeclarationKind::Const,
+                    vec![Declarator {
+                        name: String::from("c"),
+                        init: Some(Expression::Identifier(
+                            String::from("a"),
+                            Position {
+                                line: 1,
+                                column: 25
+                            },
+                        )),
+                        position: at(21),
+                    }]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_left_associative_operators() {
+        let expression = Parser::new("2 * 3 * 4 - 1").unwrap().expression().unwrap();
+        assert_eq!(
+            expression,
+            Expression::Binary(
+                BinaryOperator::Sub,
+                Box::new(Expression::Binary(
+                    BinaryOperator::Mul,
+                    Box::new(Expression::Binary(
+                        BinaryOperator::Mul,
+                        number(2f64),
+                        number(3f64),
+                        at(3),
+                    )),
+                    number(4f64),
+                    at(7),
+                )),
+                number(1f64),
+                at(11),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_parentheses() {
+        let expression = Parser::new("4 * (4 + 2)").unwrap().expression().unwrap();
+        assert_eq!(
+            expression,
+            Expression::Binary(
+                BinaryOperator::Mul,
+                number(4f64),
+                Box::new(Expression::Binary(
+                    BinaryOperator::Add,
+                    number(4f64),
+                    number(2f64),
+                    at(8),
+                )),
+                at(3),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_assignment() {
+        let expression = Parser::new("a = b = 1").unwrap().expression().unwrap();
+        assert_eq!(
+            expression,
+            Expression::Assign(
+                AssignmentOperator::Assign,
+                identifier("a", 1),
+                Box::new(Expression::Assign(
+                    AssignmentOperator::Assign,
+                    identifier("b", 5),
+                    number(1f64),
+                    at(5),
+                )),
+                at(1),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_unexpected_token() {
+        let error = Parser::new("var a = 1;\nvar = 2;")
+            .unwrap()
+            .program()
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::SyntaxError);
+        assert_eq!(error.message, "Unexpected token =");
+        assert_eq!(error.position, Some(Position { line: 2, column: 5 }));
+    }
+
+    #[test]
+    fn parse_unexpected_end_of_input() {
+        let error = Parser::new("function f() { var a = (1")
+            .unwrap()
+            .program()
+            .unwrap_err();
+        assert_eq!(error.message, "Unexpected end of input");
+    }
+
+    #[test]
+    fn parse_invalid_assignment_target() {
+        let error = Parser::new("1 + a = 2").unwrap().expression().unwrap_err();
+        assert_eq!(error.message, "Invalid left-hand side in assignment");
+    }
+
+    #[test]
+    fn parse_member_call() {
+        let expression = Parser::new("console.log(1, a)")
+            .unwrap()
+            .expression()
+            .unwrap();
+        assert_eq!(
+            expression,
+            Expression::Call(
+                Box::new(Expression::Member(
+                    Box::new(Expression::Identifier(
+                        String::from("console"),
+                        Position { line: 1, column: 1 },
+                    )),
+                    String::from("log"),
+                    Position { line: 1, column: 8 },
+                )),
+                vec![
+                    Expression::Number(1f64),
+                    Expression::Identifier(
+                        String::from("a"),
+                        Position {
+                            line: 1,
+                            column: 16
+                        }
+                    ),
+                ],
+                Position {
+                    line: 1,
+                    column: 12
+                },
+            )
+        );
+    }
+
+    #[test]
+    fn parse_function_declaration() {
+        let program = Parser::new(
+            "function myFunction() {
+                var a = 10;
+                { a; }
+            }",
+        )
+        .unwrap()
+        .program()
+        .unwrap();
+        match &program.body[..] {
+            [Statement::Function(function)] => {
+                assert_eq!(function.name, "myFunction");
+                assert!(function.params.is_empty());
+                assert_eq!(function.body.len(), 2);
+            }
+            actual => panic!("Expected function declaration found {:?}", actual),
+        }
+    }
+
+    #[test]
+    fn parse_redeclarations() {
+        let error = Parser::new("let a = 1;\nlet a = 2;")
+            .unwrap()
+            .program()
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::SyntaxError);
+        assert_eq!(error.message, "Identifier 'a' has already been declared");
+        assert_eq!(error.position, Some(Position { line: 2, column: 5 }));
+        for source in [
+            "var a; const a = 1;",
+            "const a = 1; var a;",
+            "let a; { var a; }",
+            "let a; function a() {}",
+        ] {
+            let error = Parser::new(source).unwrap().program().unwrap_err();
+            assert_eq!(error.kind, ErrorKind::SyntaxError, "{}", source);
+        }
+        for source in [
+            "var a; var a;",
+            "let a; { let a; }",
+            "{ let a; } { const a = 1; }",
+            "let a; function f() { var a; }",
+        ] {
+            assert!(Parser::new(source).unwrap().program().is_ok(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_block_function_declarations() {
+        for source in [
+            "{ let f; function f() {} }",
+            "{ function f() {} let f; }",
+            "{ function f() {} var f; }",
+            "'use strict'; { function f() {} function f() {} }",
+            "while (a) function f() {}",
+            "a: function f() {}",
+            "'use strict'; if (a) function f() {}",
+        ] {
+            let error = Parser::new(source).unwrap().program().unwrap_err();
+            assert_eq!(error.kind, ErrorKind::SyntaxError, "{}", source);
+        }
+        for source in [
+            "let a = 1; { function a() {} }",
+            "{ function f() {} function f() {} }",
+            "{ function f() {} } var f; let g; { function g() {} }",
+            "if (a) function f() {} else function g() {}",
+        ] {
+            assert!(Parser::new(source).unwrap().program().is_ok(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_annex_b_hoisting() {
+        // Whether each block function is also hoisted to a `var`, in source order.
+        fn hoisted(source: &str) -> Vec<bool> {
+            fn collect(body: &[Statement], hoisted: &mut Vec<bool>) {
+                for statement in body {
+                    match statement {
+                        Statement::Function(function) => {
+                            hoisted.push(function.annex_b);
+                            collect(&function.body, hoisted);
+                        }
+                        Statement::Block(body) => collect(body, hoisted),
+                        Statement::For(_, _, _, body) => collect(slice::from_ref(body), hoisted),
+                        _ => (),
+                    }
+                }
+            }
+            let program = Parser::new(source).unwrap().program().unwrap();
+            let mut hoisted = Vec::new();
+            collect(&program.body, &mut hoisted);
+            hoisted
+        }
+        assert_eq!(
+            hoisted("function f() {} { function g() {} }"),
+            [false, true]
+        );
+        assert_eq!(hoisted("{ function a() {} } let a;"), [false]);
+        assert_eq!(hoisted("{ let a; { function a() {} } }"), [false]);
+        assert_eq!(
+            hoisted("{ function a() {} { function a() {} } }"),
+            [true, false]
+        );
+        assert_eq!(hoisted("for (let a;;) { function a() {} }"), [false]);
+        assert_eq!(
+            hoisted("function f(a) { { function a() {} } }"),
+            [false, false]
+        );
+        assert_eq!(hoisted("'use strict'; { function a() {} }"), [false]);
+    }
+
+    #[test]
+    fn parse_const_without_initializer() {
+        let error = Parser::new("const a;").unwrap().program().unwrap_err();
+        assert_eq!(error.message, "Missing initializer in const declaration");
+    }
+
+    #[test]
+    fn parse_params_and_return() {
+        let program = Parser::new("function add(a, b) { return a + b; }")
+            .unwrap()
+            .program()
+            .unwrap();
+        match &program.body[..] {
+            [Statement::Function(function)] => {
+                assert_eq!(*function.params, ["a", "b"]);
+                assert!(matches!(
+                    function.body[..],
+                    [Statement::Return(Some(Expression::Binary(
+                        BinaryOperator::Add,
+                        _,
+                        _,
+                        _
+                    )))]
+                ));
+            }
+            actual => panic!("Expected function declaration found {:?}", actual),
+        }
+        let error = Parser::new("function f(a) { let a; }")
+            .unwrap()
+            .program()
+            .unwrap_err();
+        assert_eq!(error.message, "Identifier 'a' has already been declared");
+    }
+
+    #[test]
+    fn parse_if_else() {
+        let program = Parser::new("if (a) b; else if (c) { d; } else e;")
+            .unwrap()
+            .program()
+            .unwrap();
+        match &program.body[..] {
+            [Statement::If(_, consequent, Some(alternate))] => {
+                assert!(matches!(**consequent, Statement::Expression(_)));
+                assert!(matches!(
+                    **alternate,
+                    Statement::If(_, _, Some(ref last)) if matches!(**last, Statement::Expression(_))
+                ));
+            }
+            actual => panic!("Expected if statement found {:?}", actual),
+        }
+        let error = Parser::new("if (a) let b = 1;")
+            .unwrap()
+            .program()
+            .unwrap_err();
+        assert_eq!(
+            error.message,
+            "Lexical declaration cannot appear in a single-statement context"
+        );
+    }
+
+    #[test]
+    fn parse_conditional_and_logical_precedence() {
+        let expression = Parser::new("a || b && c ? 1 : 2")
+            .unwrap()
+            .expression()
+            .unwrap();
+        let identifier = |name: &str, column| {
+            Box::new(Expression::Identifier(
+                String::from(name),
+                Position { line: 1, column },
+            ))
+        };
+        assert_eq!(
+            expression,
+            Expression::Conditional(
+                Box::new(Expression::Logical(
+                    LogicalOperator::Or,
+                    identifier("a", 1),
+                    Box::new(Expression::Logical(
+                        LogicalOperator::And,
+                        identifier("b", 6),
+                        identifier("c", 11),
+                    )),
+                )),
+                number(1f64),
+                number(2f64),
+            )
+        );
+    }
+
+    /// Renders an expression as an S-expression so precedence is easy to read in tests.
+    fn tree(expression: &Expression) -> String {
+        match expression {
+            Expression::Number(number) => number.to_string(),
+            Expression::Identifier(name, _) => name.clone(),
+            Expression::Binary(operator, left, right, _) => {
+                format!("({} {} {})", operator, tree(left), tree(right))
+            }
+            Expression::Logical(operator, left, right) => {
+                format!("({} {} {})", operator, tree(left), tree(right))
+            }
+            Expression::Conditional(test, consequent, alternate) => format!(
+                "(? {} {} {})",
+                tree(test),
+                tree(consequent),
+                tree(alternate)
+            ),
+            Expression::Unary(operator, operand, _) => format!("({} {})", operator, tree(operand)),
+            Expression::Update(operator, true, operand, _) => {
+                format!("({} {})", operator, tree(operand))
+            }
+            Expression::Update(operator, false, operand, _) => {
+                format!("({} {})", tree(operand), operator)
+            }
+            Expression::Assign(operator, target, value, _) => {
+                let operator = match operator {
+                    AssignmentOperator::Assign => String::from("="),
+                    AssignmentOperator::Binary(operator) => format!("{}=", operator),
+                    AssignmentOperator::Logical(operator) => format!("{}=", operator),
+                };
+                format!("({} {} {})", operator, tree(target), tree(value))
+            }
+            Expression::Sequence(expressions) => {
+                let expressions: Vec<String> = expressions.iter().map(tree).collect();
+                format!("(, {})", expressions.join(" "))
+            }
+            Expression::Member(object, property, _) => format!("{}.{}", tree(object), property),
+            Expression::Call(callee, arguments, _) => {
+                let arguments: Vec<String> = arguments.iter().map(tree).collect();
+                format!("(call {} {})", tree(callee), arguments.join(" "))
+            }
+            expression => format!("{:?}", expression),
+        }
+    }
+
+    fn parse_tree(source: &str) -> String {
+        tree(&Parser::new(source).unwrap().expression().unwrap())
+    }
+
+    #[test]
+    fn parse_precedence_table() {
+        for (source, expected) in [
+            ("1 + 2 * 3 - 4 / 2 % 5", "(- (+ 1 (* 2 3)) (% (/ 4 2) 5))"),
+            ("(a || b) ?? c", "(?? (|| a b) c)"),
+            ("a ?? (b && c)", "(?? a (&& b c))"),
+            ("a ?? b ?? c | d", "(?? (?? a b) (| c d))"),
+            ("a | b ^ c & d", "(| a (^ b (& c d)))"),
+            ("a == b < c << d + e", "(== a (< b (<< c (+ d e))))"),
+            ("a !== b === c", "(=== (!== a b) c)"),
+            ("a >>> b >> c", "(>> (>>> a b) c)"),
+            ("a in b instanceof c", "(instanceof (in a b) c)"),
+            ("a && b || c && d", "(|| (&& a b) (&& c d))"),
+            ("-a * b", "(* (- a) b)"),
+            ("!typeof void a", "(! (typeof (void a)))"),
+            ("- -a", "(- (- a))"),
+            ("+a.b(c)", "(+ (call a.b c))"),
+            ("a++ + ++b", "(+ (a ++) (++ b))"),
+            ("-a++", "(- (a ++))"),
+            ("delete a.b", "(delete a.b)"),
+        ] {
+            assert_eq!(parse_tree(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_right_associative_operators() {
+        for (source, expected) in [
+            ("2 ** 3 ** 2", "(** 2 (** 3 2))"),
+            ("2 * 3 ** 2", "(* 2 (** 3 2))"),
+            ("(-2) ** 2", "(** (- 2) 2)"),
+            ("a = b += c ??= d", "(= a (+= b (??= c d)))"),
+            ("a ? b : c ? d : e", "(? a b (? c d e))"),
+            ("a ? b = 1 : c = 2", "(? a (= b 1) (= c 2))"),
+            ("a = b ? c : d", "(= a (? b c d))"),
+            ("a || b ? c : d", "(? (|| a b) c d)"),
+        ] {
+            assert_eq!(parse_tree(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_comma_operator() {
+        assert_eq!(parse_tree("a = 1, b = 2, c"), "(, (= a 1) (= b 2) c)");
+        assert_eq!(parse_tree("f(a, (b, c))"), "(call f a (, b c))");
+    }
+
+    #[test]
+    fn parse_operator_errors() {
+        for (source, message) in [
+            (
+                "-2 ** 2",
+                "Unary operator used immediately before exponentiation expression. \
+                Parenthesis must be used to disambiguate operator precedence",
+            ),
+            ("a + b = c", "Invalid left-hand side in assignment"),
+            ("a += 1 = 2", "Invalid left-hand side in assignment"),
+            (
+                "++a()",
+                "Invalid left-hand side expression in prefix operation",
+            ),
+            (
+                "1++",
+                "Invalid left-hand side expression in postfix operation",
+            ),
+            ("a || b ?? c", "Unexpected token ??"),
+            ("a && b ?? c", "Unexpected token ??"),
+            ("a ?? b || c", "Unexpected token ||"),
+            ("a ?? b && c", "Unexpected token &&"),
+            ("(a ?? b) || c ?? d", "Unexpected token ??"),
+        ] {
+            let error = Parser::new(source).unwrap().expression().unwrap_err();
+            assert_eq!(error.kind, ErrorKind::SyntaxError, "{}", source);
+            assert_eq!(error.message, message, "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_automatic_semicolons() {
+        for source in [
+            "var x = 1\nvar y = 2",
+            "a = 1\n/* comment */ b = 2",
+            "a = 1 /*\n*/ b = 2",
+            "a = 1 // comment\nb = 2",
+            "{ a = 1 }",
+            "a = 1",
+            "do {} while (a) a = 1",
+            "x\n`\n` + y",
+        ] {
+            assert!(Parser::new(source).unwrap().program().is_ok(), "{}", source);
+        }
+        for (source, message, column) in [
+            ("var x = 1 2", "Unexpected token 2", 11),
+            (
+                "console.log(1) console.log(2)",
+                "Unexpected token console",
+                16,
+            ),
+            ("a = `\n` b", "Unexpected token b", 3),
+            ("if (a) b = 1 c = 2", "Unexpected token c", 14),
+        ] {
+            let error = Parser::new(source)
+                .and_then(|mut parser| parser.program())
+                .unwrap_err();
+            assert_eq!(error.message, message, "{}", source);
+            assert_eq!(error.position.unwrap().column, column, "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_postfix_update_on_next_line() {
+        let program = Parser::new("a\n++b").unwrap().program().unwrap();
+        assert_eq!(program.body.len(), 2);
+        let program = Parser::new("a /*\n*/ ++b").unwrap().program().unwrap();
+        assert_eq!(program.body.len(), 2);
+    }
+
+    #[test]
+    fn parse_line_breaks_in_comments_end_restricted_statements() {
+        let program = Parser::new("function f() { return /*\n*/ 1 }")
+            .unwrap()
+            .program()
+            .unwrap();
+        match &program.body[0] {
+            Statement::Function(function) => {
+                assert_eq!(function.body[0], Statement::Return(None));
+                assert_eq!(function.body.len(), 2);
+            }
+            actual => panic!("Expected function found {:?}", actual),
+        }
+        let program = Parser::new("x: while (1) { break /*\n*/ x }")
+            .unwrap()
+            .program()
+            .unwrap();
+        match &program.body[0] {
+            Statement::Labeled(_, body) => match &**body {
+                Statement::While(_, body) => match &**body {
+                    Statement::Block(body) => {
+                        assert_eq!(body[0], Statement::Break(None));
+                        assert_eq!(body.len(), 2);
+                    }
+                    actual => panic!("Expected block found {:?}", actual),
+                },
+                actual => panic!("Expected while found {:?}", actual),
+            },
+            actual => panic!("Expected label found {:?}", actual),
+        }
+    }
+
+    #[test]
+    fn parse_legacy_octal_in_strict_mode() {
+        for source in [
+            "017",
+            "08",
+            "'use strict' + 1; 017",
+            "('use strict'); 017",
+            "function f() { 'use strict' } 017",
+        ] {
+            assert!(Parser::new(source).unwrap().program().is_ok(), "{}", source);
+        }
+        for (source, message) in [
+            (
+                "'use strict'; 017",
+                "Octal literals are not allowed in strict mode.",
+            ),
+            (
+                "\"use strict\"\n08",
+                "Decimals with leading zeros are not allowed in strict mode.",
+            ),
+            (
+                "'a'; 'use strict'; 00",
+                "Octal literals are not allowed in strict mode.",
+            ),
+            (
+                "function f() { 'use strict'; return 07 }",
+                "Octal literals are not allowed in strict mode.",
+            ),
+            (
+                "'use strict'; var f = () => { return 07 }",
+                "Octal literals are not allowed in strict mode.",
+            ),
+        ] {
+            let error = Parser::new(source).unwrap().program().unwrap_err();
+            assert_eq!(error.message, message, "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_legacy_escapes_in_strict_mode() {
+        for source in [
+            r"'\07'",
+            r"'\8'",
+            r"'use strict'; '\0'",
+            r"'use strict'; '\\07'",
+        ] {
+            assert!(Parser::new(source).unwrap().program().is_ok(), "{}", source);
+        }
+        for (source, message) in [
+            (
+                r"'use strict'; '\07'",
+                "Octal escape sequences are not allowed in strict mode.",
+            ),
+            (
+                r"'use strict'; '\00'",
+                "Octal escape sequences are not allowed in strict mode.",
+            ),
+            (
+                r"function f() { 'use strict'; return '\9' }",
+                r"\8 and \9 are not allowed in strict mode.",
+            ),
+        ] {
+            let error = Parser::new(source).unwrap().program().unwrap_err();
+            assert_eq!(error.message, message, "{}", source);
+        }
+    }
+
+    fn element(cooked: Option<&str>, raw: &str) -> TemplateElement {
+        TemplateElement {
+            cooked: cooked.map(String::from),
+            raw: String::from(raw),
+        }
+    }
+
+    #[test]
+    fn parse_template() {
+        let expression = Parser::new("`a${b}\\n${`c${d}`}`")
+            .unwrap()
+            .expression()
+            .unwrap();
+        assert_eq!(
+            expression,
+            Expression::Template(
+                vec![
+                    element(Some("a"), "a"),
+                    element(Some("\n"), "\\n"),
+                    element(Some(""), ""),
+                ],
+                vec![
+                    *identifier("b", 5),
+                    Expression::Template(
+                        vec![element(Some("c"), "c"), element(Some(""), "")],
+                        vec![*identifier("d", 15)],
+                        at(11),
+                    ),
+                ],
+                at(1),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_tagged_template() {
+        let expression = Parser::new("a.b`\\unicode ${1}`")
+            .unwrap()
+            .expression()
+            .unwrap();
+        assert_eq!(
+            expression,
+            Expression::TaggedTemplate(
+                Box::new(Expression::Member(
+                    identifier("a", 1),
+                    String::from("b"),
+                    at(2)
+                )),
+                vec![element(None, "\\unicode "), element(Some(""), "")],
+                vec![Expression::Number(1f64)],
+                at(4),
+            )
+        );
+        let program = Parser::new("tag\n`a`").unwrap().program().unwrap();
+        assert_eq!(program.body.len(), 1);
+    }
+
+    #[test]
+    fn parse_template_errors() {
+        for (source, message, column) in [
+            ("`\\unicode`", "Invalid Unicode escape sequence", 1),
+            (
+                "`\\01`",
+                "Octal escape sequences are not allowed in template strings",
+                1,
+            ),
+            ("`${a b}`", "Unexpected token b", 6),
+            ("1 + `${1 +}`", "Unexpected token }", 11),
+            ("`${1`", "Unterminated template literal", 1),
+        ] {
+            let error = Parser::new(source)
+                .and_then(|mut parser| parser.program())
+                .unwrap_err();
+            assert_eq!(error.message, message, "{}", source);
+            assert_eq!(error.position, Some(at(column)), "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_array_literal() {
+        let expression = Parser::new("[1, [a], ]").unwrap().expression().unwrap();
+        assert_eq!(
+            expression,
+            Expression::Array(vec![
+                Expression::Number(1f64),
+                Expression::Array(vec![*identifier("a", 6)]),
+            ])
+        );
+        assert_eq!(
+            Parser::new("[]").unwrap().expression().unwrap(),
+            Expression::Array(vec![])
+        );
+        let error = Parser::new("[1 2]").unwrap().expression().unwrap_err();
+        assert_eq!(error.message, "Unexpected token 2");
+    }
+
+    #[test]
+    fn parse_object_literal() {
+        let named =
+            |name: &str, value| Property::Init(PropertyKey::Named(String::from(name)), value);
+        let expression = Parser::new("{a: 1, 'b-c': a, 2: null, if: {}, 0x10: 1,}")
+            .unwrap()
+            .expression()
+            .unwrap();
+        assert_eq!(
+            expression,
+            Expression::Object(vec![
+                named("a", Expression::Number(1f64)),
+                named("b-c", *identifier("a", 15)),
+                named("2", Expression::Null),
+                named("if", Expression::Object(vec![])),
+                named("16", Expression::Number(1f64)),
+            ])
+        );
+        let error = Parser::new("{a 1}").unwrap().expression().unwrap_err();
+        assert_eq!(error.message, "Unexpected token 1");
+    }
+
+    #[test]
+    fn parse_object_shorthand_computed_methods_and_spread() {
+        let expression = Parser::new("{a, [a]: 1, m(x) { return x }, ...a}")
+            .unwrap()
+            .expression()
+            .unwrap();
+        assert_eq!(
+            expression,
+            Expression::Object(vec![
+                Property::Init(PropertyKey::Named(String::from("a")), *identifier("a", 2)),
+                Property::Init(
+                    PropertyKey::Computed(*identifier("a", 6), at(5)),
+                    Expression::Number(1f64)
+                ),
+                Property::Init(
+                    PropertyKey::Named(String::from("m")),
+                    Expression::Function(FunctionDeclaration {
+                        name: String::new(),
+                        params: Rc::from([String::from("x")]),
+                        body: Rc::from([Statement::Return(Some(*identifier("x", 27)))]),
+                        source: Rc::from("m(x) { return x }"),
+                        position: at(13),
+                        annex_b: false,
+                    })
+                ),
+                Property::Spread(*identifier("a", 35)),
+            ])
+        );
+        for (source, message) in [
+            ("({true})", "Unexpected token }"),
+            ("({a = 1})", "Unexpected token ="),
+            ("({...})", "Unexpected token }"),
+            (
+                "'use strict'; ({010: 1})",
+                "Octal literals are not allowed in strict mode.",
+            ),
+        ] {
+            let error = Parser::new(source)
+                .and_then(|mut parser| parser.program())
+                .unwrap_err();
+            assert_eq!(error.message, message, "{}", source);
+        }
+    }
+}
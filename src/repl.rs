@@ -0,0 +1,213 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use rustjs::{Context, ErrorKind, JsError, Value};
+
+const PROMPT: &str = "> ";
+const CONTINUATION: &str = "... ";
+
+const HELP: &str = ".clear    Forget all variables and any partially entered input
+.exit     Exit the REPL
+.help     Print this help message
+.load     Run a JavaScript file in the current session, e.g. .load file.js";
+
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+    /// The input so far is not a complete program; keep reading lines.
+    Incomplete,
+    Output(String),
+    Exit,
+}
+
+/// Reads and runs one line at a time against a single long-lived context.
+pub struct Repl {
+    context: Context,
+    buffer: String,
+}
+
+/// Whether `error` was caused by `source` stopping part way through a construct. Template
+/// literals and comments can span lines, but a quoted string only continues onto the next line
+/// after a trailing `\`; otherwise more input can never close it.
+fn is_incomplete(error: &JsError, source: &str) -> bool {
+    let continues_line = source.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1;
+    error.kind == ErrorKind::SyntaxError
+        && match error.message.as_str() {
+            "Unexpected end of input"
+            | "Unterminated template literal"
+            | "Unterminated comment" => true,
+            "Unterminated string literal" => continues_line,
+            _ => false,
+        }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            context: Context::new(),
+            buffer: String::new(),
+        }
+    }
+
+    pub fn line(&mut self, line: &str) -> Reply {
+        if self.buffer.is_empty() && line.trim_start().starts_with('.') {
+            return self.command(line.trim());
+        }
+
+        if !self.buffer.is_empty() {
+            self.buffer.push('\n');
+        }
+        self.buffer.push_str(line);
+        let source = self.buffer.clone();
+        match self.context.eval(&source) {
+            Err(error) if is_incomplete(&error, &source) => Reply::Incomplete,
+            result => {
+                self.buffer.clear();
+                Reply::Output(Self::format(result))
+            }
+        }
+    }
+
+    fn format(result: Result<Value, JsError>) -> String {
+        match result {
+            Ok(value) => value.inspect(),
+            Err(error) => format!("Uncaught {}", error),
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Reply {
+        let mut parts = command.splitn(2, char::is_whitespace);
+        match (parts.next(), parts.next().map(str::trim)) {
+            (Some(".exit"), _) => Reply::Exit,
+            (Some(".clear"), _) => {
+                *self = Self::new();
+                Reply::Output(String::from("Clearing context..."))
+            }
+            (Some(".help"), _) => Reply::Output(String::from(HELP)),
+            (Some(".load"), Some(file)) if !file.is_empty() => match fs::read_to_string(file) {
+                Ok(source) => Reply::Output(Self::format(self.context.eval(&source))),
+                Err(error) => Reply::Output(format!("Failed to load {}: {}", file, error)),
+            },
+            (Some(".load"), _) => Reply::Output(String::from("Usage: .load file.js")),
+            (Some(command), _) => Reply::Output(format!("Invalid REPL keyword {}", command)),
+            (None, _) => Reply::Incomplete,
+        }
+    }
+}
+
+/// Runs the REPL on stdin and stdout until `.exit` or end of input.
+pub fn run() -> i32 {
+    let mut repl = Repl::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut prompt = PROMPT;
+    loop {
+        print!("{}", prompt);
+        io::stdout().flush().ok();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(error)) => {
+                eprintln!("Error: {}", error);
+                return 1;
+            }
+            None => {
+                println!();
+                return 0;
+            }
+        };
+        prompt = match repl.line(&line) {
+            Reply::Incomplete => CONTINUATION,
+            Reply::Output(output) => {
+                println!("{}", output);
+                PROMPT
+            }
+            Reply::Exit => return 0,
+        };
+    }
+}
+
+#[cfg(test)]
+mod repl_tests {
+    use super::*;
+
+    fn output(output: &str) -> Reply {
+        Reply::Output(String::from(output))
+    }
+
+    #[test]
+    fn keeps_state_between_lines() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.line("var a = 10;"), output("undefined"));
+        assert_eq!(repl.line("a + 5"), output("15"));
+        assert_eq!(repl.line("'jake'"), output("'jake'"));
+    }
+
+    #[test]
+    fn continues_incomplete_input() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.line("function f() {"), Reply::Incomplete);
+        assert_eq!(repl.line("  var a = (1 +"), Reply::Incomplete);
+        assert_eq!(repl.line("  2);"), Reply::Incomplete);
+        assert_eq!(repl.line("}"), output("undefined"));
+        assert_eq!(repl.line("`a ${1 +"), Reply::Incomplete);
+        assert_eq!(repl.line("1} b"), Reply::Incomplete);
+        assert_eq!(repl.line("c`"), output("'a 2 b\\nc'"));
+        assert_eq!(repl.line("'continued \\"), Reply::Incomplete);
+        assert_eq!(repl.line("string'"), output("'continued string'"));
+        assert_eq!(repl.line("/* a"), Reply::Incomplete);
+        assert_eq!(repl.line("b"), Reply::Incomplete);
+        assert_eq!(repl.line("*/ 1"), output("1"));
+    }
+
+    #[test]
+    fn reports_unterminated_strings_at_once() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.line("'unclosed"),
+            output("Uncaught SyntaxError: Unterminated string literal (1:1)")
+        );
+        assert_eq!(
+            repl.line("\"not a continuation \\\\"),
+            output("Uncaught SyntaxError: Unterminated string literal (1:1)")
+        );
+        assert_eq!(repl.line("1 + 1"), output("2"));
+    }
+
+    #[test]
+    fn reports_errors_and_recovers() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.line("missing"),
+            output("Uncaught ReferenceError: missing is not defined (1:1)")
+        );
+        assert_eq!(repl.line("1 + 1"), output("2"));
+    }
+
+    #[test]
+    fn clear_forgets_variables() {
+        let mut repl = Repl::new();
+        repl.line("var a = 1;");
+        assert_eq!(repl.line(".clear"), output("Clearing context..."));
+        assert_eq!(
+            repl.line("a"),
+            output("Uncaught ReferenceError: a is not defined (1:1)")
+        );
+    }
+
+    #[test]
+    fn load_runs_file_in_session() {
+        let path = std::env::temp_dir().join("rustjs_repl_load_test.js");
+        fs::write(&path, "var loaded = 41;\nloaded + 1;").unwrap();
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.line(&format!(".load {}", path.display())),
+            output("42")
+        );
+        assert_eq!(repl.line("loaded"), output("41"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn exit_command() {
+        assert_eq!(Repl::new().line(".exit"), Reply::Exit);
+    }
+}
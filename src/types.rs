@@ -1,29 +1,105 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::ops::{Add, Div, Mul, Sub};
-use std::string::ToString;
+use std::fmt;
+use std::mem;
+use std::ops::{Add, Div, Index, Mul, Neg, Rem, Sub};
+use std::rc::Rc;
 
-#[derive(Debug, Copy, PartialEq)]
+use crate::ast::Statement;
+use crate::environment::Environment;
+use crate::error::JsError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Num {
     NaN,
     F64(f64),
 }
 
-impl Clone for Num {
-    fn clone(&self) -> Self {
-        use Num::*;
+/// Formats the number the way the spec's Number::toString does, which is how JavaScript turns
+/// numbers into strings.
+impl fmt::Display for Num {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let number = f64::from(*self);
+        if number.is_nan() {
+            return write!(f, "NaN");
+        }
+        if number == 0f64 {
+            // Both zeros are written as `0`.
+            return write!(f, "0");
+        }
+        if number < 0f64 {
+            write!(f, "-")?;
+        }
+        let number = number.abs();
+        if number.is_infinite() {
+            return write!(f, "Infinity");
+        }
+        // Rust's scientific notation gives the shortest digits that round trip, which are the
+        // digits the spec asks for. `n` is where the decimal point goes relative to them.
+        let scientific = format!("{:e}", number);
+        let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+        let digits = mantissa.replace('.', "");
+        let k = digits.len() as i32;
+        let n = exponent.parse::<i32>().unwrap_or(0) + 1;
+        if k <= n && n <= 21 {
+            write!(f, "{}{}", digits, "0".repeat((n - k) as usize))
+        } else if 0 < n && n <= 21 {
+            let (integer, fraction) = digits.split_at(n as usize);
+            write!(f, "{}.{}", integer, fraction)
+        } else if -6 < n && n <= 0 {
+            write!(f, "0.{}{}", "0".repeat(-n as usize), digits)
+        } else {
+            let sign = if n > 0 { '+' } else { '-' };
+            let (first, rest) = digits.split_at(1);
+            let point = if rest.is_empty() { "" } else { "." };
+            write!(f, "{}{}{}e{}{}", first, point, rest, sign, (n - 1).abs())
+        }
+    }
+}
+
+impl From<Num> for f64 {
+    fn from(number: Num) -> f64 {
+        match number {
+            Num::NaN => f64::NAN,
+            Num::F64(number) => number,
+        }
+    }
+}
+
+impl Num {
+    /// The `**` operator, which unlike `powf` gives NaN for `1 ** Infinity` and `1 ** NaN`.
+    pub fn pow(self, exponent: Num) -> Num {
+        let (base, exponent) = (f64::from(self), f64::from(exponent));
+        if exponent.is_nan() || (base.abs() == 1f64 && exponent.is_infinite()) {
+            return Num::NaN;
+        }
+        Num::F64(base.powf(exponent))
+    }
+
+    /// The spec's ToInt32: truncates towards zero and wraps modulo 2^32, with NaN and the
+    /// infinities becoming 0.
+    pub fn to_int32(self) -> i32 {
+        self.to_uint32() as i32
+    }
+
+    /// The spec's ToUint32, which wraps the same way as `to_int32` but stays unsigned.
+    pub fn to_uint32(self) -> u32 {
         match self {
-            NaN => NaN,
-            F64(x) => F64(*x),
+            Num::F64(number) if number.is_finite() => {
+                number.trunc().rem_euclid(4294967296f64) as u32
+            }
+            _ => 0,
         }
     }
 }
 
-impl ToString for Num {
-    fn to_string(&self) -> String {
-        use Num::*;
+impl Neg for Num {
+    type Output = Num;
+
+    fn neg(self) -> Self::Output {
         match self {
-            NaN => String::from("NaN"),
-            F64(num) => num.to_string(),
+            Num::NaN => Num::NaN,
+            Num::F64(number) => Num::F64(-number),
         }
     }
 }
@@ -76,134 +152,809 @@ impl Div for Num {
     }
 }
 
-#[derive(Debug)]
-pub enum Type<'a> {
+/// The `%` operator, which like Rust's takes the sign of the dividend.
+impl Rem for Num {
+    type Output = Num;
+
+    fn rem(self, rhs: Num) -> Self::Output {
+        use Num::*;
+        match (self, rhs) {
+            (F64(l), F64(r)) if r != 0f64 && l.is_finite() => F64(l % r),
+            _ => NaN,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Type {
     Null,
     Undefined,
     Number(Num),
     Boolean(bool),
     TextString(String),
-    Object(&'a Box<HashMap<String, Type<'a>>>),
-    Function(String),
+    Object(Rc<RefCell<Properties>>),
+    Array(Rc<RefCell<Vec<Type>>>),
+    Function(Rc<Function>),
 }
 
-impl<'a> Add for Type<'a> {
-    type Output = Type<'a>;
+/// An object's properties. Like a JavaScript object, and unlike a `HashMap`, it remembers the
+/// order keys were added in, and lists keys that are array indexes first in ascending order.
+#[derive(Debug, Clone, Default)]
+pub struct Properties {
+    entries: Vec<(String, Type)>,
+    /// Where each key's entry is in `entries`.
+    positions: HashMap<String, usize>,
+}
 
-    fn add(self, rhs: Type) -> Self::Output {
+impl Properties {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Type> {
+        self.positions
+            .get(key)
+            .map(|&position| &self.entries[position].1)
+    }
+
+    /// Sets `key` to `value`. A new key goes last, while an existing one keeps its place.
+    pub fn insert(&mut self, key: String, value: Type) -> Option<Type> {
+        match self.positions.get(&key) {
+            Some(&position) => Some(mem::replace(&mut self.entries[position].1, value)),
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Type> {
+        let position = self.positions.remove(key)?;
+        let (_, value) = self.entries.remove(position);
+        for (key, _) in &self.entries[position..] {
+            *self.positions.get_mut(key).unwrap() -= 1;
+        }
+        Some(value)
+    }
+
+    /// The properties in the order the spec lists an object's own keys: array indexes in
+    /// ascending order, then every other key in the order it was added.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Type)> {
+        let mut indexes: Vec<(usize, &(String, Type))> = self
+            .entries
+            .iter()
+            .filter_map(|entry| array_index(&entry.0).map(|index| (index, entry)))
+            .collect();
+        indexes.sort_by_key(|&(index, _)| index);
+        let others = self
+            .entries
+            .iter()
+            .filter(|(key, _)| array_index(key).is_none());
+        indexes
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .chain(others)
+            .map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.iter().map(|(key, _)| key)
+    }
+}
+
+impl Index<&str> for Properties {
+    type Output = Type;
+
+    fn index(&self, key: &str) -> &Type {
+        self.get(key).expect("no property with that key")
+    }
+}
+
+impl Extend<(String, Type)> for Properties {
+    fn extend<I: IntoIterator<Item = (String, Type)>>(&mut self, properties: I) {
+        for (key, value) in properties {
+            self.insert(key, value);
+        }
+    }
+}
+
+/// The most elements an array may grow to. Arrays are a `Vec` without holes, so a length or
+/// index beyond this throws a `RangeError` rather than allocating every element up to it.
+pub(crate) const MAX_ARRAY_LENGTH: usize = 1 << 24;
+
+/// The index a property key refers to, if it is one, i.e. it is how an integer is written.
+pub(crate) fn array_index(key: &str) -> Option<usize> {
+    key.parse::<usize>()
+        .ok()
+        .filter(|index| index.to_string() == key)
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use Type::*;
         match self {
-            Null => panic!("Not implemented!"),
-            Undefined => match rhs {
-                Number(_) => Number(Num::NaN),
-                _ => panic!("Not implemented!"),
-            },
-            Number(lhs) => match rhs {
-                Number(r) => Number(lhs + r),
-                TextString(r) => {
-                    let mut l = lhs.to_string();
-                    l.push_str(&r);
-                    TextString(l)
+            Null => write!(f, "null"),
+            Undefined => write!(f, "undefined"),
+            Number(number) => write!(f, "{}", number),
+            Boolean(boolean) => write!(f, "{}", boolean),
+            TextString(string) => write!(f, "{}", string),
+            Object(_) => write!(f, "[object Object]"),
+            Array(array) => write!(f, "{}", join(array, &mut Vec::new())),
+            // Functions convert to their source text, which native ones don't have.
+            Function(function) => match &function.body {
+                FunctionBody::Script { source, .. } => write!(f, "{}", source),
+                FunctionBody::Native(_) => {
+                    write!(f, "function {}() {{ [native code] }}", function.name)
                 }
-                _ => panic!("Not implemented!"),
             },
-            Boolean(boolean) => Boolean(boolean),
-            TextString(lhs) => match rhs {
-                TextString(r) => {
-                    let mut l = lhs;
-                    l.push_str(&r);
-                    TextString(l)
-                }
-                Number(r) => {
-                    let mut l = lhs;
-                    l.push_str(&r.to_string());
-                    TextString(l)
-                }
-                _ => panic!("Not implemented!"),
-            },
-            Object(object) => Object(object),
-            Function(name) => Function(String::from(name)),
         }
     }
 }
 
-impl<'a> Sub for Type<'a> {
-    type Output = Type<'a>;
+/// Converts `array` to a string the way `join` does, with `null` and `undefined` empty.
+/// `joining` holds the arrays already being converted further out, and an array that contains
+/// itself is empty where it repeats rather than recursing forever.
+fn join(array: &Rc<RefCell<Vec<Type>>>, joining: &mut Vec<*const RefCell<Vec<Type>>>) -> String {
+    if joining.contains(&Rc::as_ptr(array)) {
+        return String::new();
+    }
+    joining.push(Rc::as_ptr(array));
+    let elements: Vec<String> = array
+        .borrow()
+        .iter()
+        .map(|element| match element {
+            Type::Null | Type::Undefined => String::new(),
+            Type::Array(inner) => join(inner, joining),
+            element => element.to_string(),
+        })
+        .collect();
+    joining.pop();
+    elements.join(",")
+}
 
-    fn sub(self, rhs: Type) -> Self::Output {
+/// Quotes `string` the way Node does, preferring single quotes.
+fn quote(string: &str) -> String {
+    let quote = if string.contains('\'') && !string.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut quoted = String::new();
+    quoted.push(quote);
+    for c in string.chars() {
+        match c {
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            '\\' => quoted.push_str("\\\\"),
+            c if c == quote => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push(quote);
+    quoted
+}
+
+/// Parses `string` the way `Number(string)` does, following the spec's StringToNumber.
+fn string_to_number(string: &str) -> Num {
+    let string = string.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    if string.is_empty() {
+        return Num::F64(0f64);
+    }
+    let radix = match string.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        return match u128::from_str_radix(&string[2..], radix) {
+            Ok(number) if !string[2..].starts_with('+') => Num::F64(number as f64),
+            _ => Num::NaN,
+        };
+    }
+    let unsigned = string.trim_start_matches(['+', '-']);
+    if unsigned == "Infinity" && string.len() - unsigned.len() <= 1 {
+        return match string.starts_with('-') {
+            true => Num::F64(f64::NEG_INFINITY),
+            false => Num::F64(f64::INFINITY),
+        };
+    }
+    // Rust also accepts words like "inf" and "nan", which JavaScript doesn't.
+    if !string
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return Num::NaN;
+    }
+    string.parse().map_or(Num::NaN, Num::F64)
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
+
+impl Type {
+    /// Whether the value counts as true in a condition, per the spec's ToBoolean.
+    pub fn is_truthy(&self) -> bool {
         use Type::*;
         match self {
-            Null => panic!("Not implemented!"),
-            Undefined => match rhs {
-                Number(_) => Number(Num::NaN),
-                _ => panic!("Not implemented!"),
-            },
-            Number(lhs) => match rhs {
-                Number(r) => Number(lhs - r),
-                TextString(r) => {
-                    let mut l = lhs.to_string();
-                    l.push_str(&r);
-                    TextString(l)
+            Null | Undefined => false,
+            Number(Num::NaN) => false,
+            // Arithmetic can leave a NaN in `F64` as well as `Num::NaN`.
+            Number(Num::F64(number)) => !number.is_nan() && *number != 0f64,
+            Boolean(boolean) => *boolean,
+            TextString(string) => !string.is_empty(),
+            Object(_) | Array(_) | Function(_) => true,
+        }
+    }
+
+    /// The conversion ToPrimitive falls back to for values without a `valueOf` or `toString`
+    /// of their own: objects, arrays and functions become the string the built-in `toString`
+    /// gives, and primitives are unchanged.
+    pub fn to_primitive(&self) -> Type {
+        match self {
+            Type::Object(_) | Type::Array(_) | Type::Function(_) => {
+                Type::TextString(self.to_string())
+            }
+            primitive => primitive.clone(),
+        }
+    }
+
+    /// The spec's ToNumber.
+    pub fn to_number(&self) -> Num {
+        use Type::*;
+        match self {
+            Null => Num::F64(0f64),
+            Undefined => Num::NaN,
+            Number(number) => *number,
+            Boolean(boolean) => Num::F64(if *boolean { 1f64 } else { 0f64 }),
+            TextString(string) => string_to_number(string),
+            Object(_) | Array(_) | Function(_) => self.to_primitive().to_number(),
+        }
+    }
+
+    /// The string the `typeof` operator gives for the value.
+    pub fn type_of(&self) -> &'static str {
+        use Type::*;
+        match self {
+            // A historical quirk kept by the spec.
+            Null => "object",
+            Undefined => "undefined",
+            Number(_) => "number",
+            Boolean(_) => "boolean",
+            TextString(_) => "string",
+            Object(_) | Array(_) => "object",
+            Function(_) => "function",
+        }
+    }
+
+    /// The `===` operator, following the spec's IsStrictlyEqual.
+    pub fn strict_equals(&self, other: &Type) -> bool {
+        use Type::*;
+        match (self, other) {
+            (Null, Null) | (Undefined, Undefined) => true,
+            (Number(l), Number(r)) => f64::from(*l) == f64::from(*r),
+            (Boolean(l), Boolean(r)) => l == r,
+            (TextString(l), TextString(r)) => l == r,
+            (Object(l), Object(r)) => Rc::ptr_eq(l, r),
+            (Array(l), Array(r)) => Rc::ptr_eq(l, r),
+            (Function(l), Function(r)) => Rc::ptr_eq(l, r),
+            _ => false,
+        }
+    }
+
+    /// The `==` operator, following the spec's IsLooselyEqual.
+    pub fn loose_equals(&self, other: &Type) -> bool {
+        use Type::*;
+        match (self, other) {
+            (Null | Undefined, Null | Undefined) => true,
+            (Null | Undefined, _) | (_, Null | Undefined) => false,
+            (Number(_), TextString(_)) | (TextString(_), Number(_)) => {
+                f64::from(self.to_number()) == f64::from(other.to_number())
+            }
+            (Boolean(_), _) => Number(self.to_number()).loose_equals(other),
+            (_, Boolean(_)) => self.loose_equals(&Number(other.to_number())),
+            (Object(_) | Array(_) | Function(_), Number(_) | TextString(_)) => {
+                self.to_primitive().loose_equals(other)
+            }
+            (Number(_) | TextString(_), Object(_) | Array(_) | Function(_)) => {
+                self.loose_equals(&other.to_primitive())
+            }
+            _ => self.strict_equals(other),
+        }
+    }
+
+    /// The spec's IsLessThan, which is `None` (undefined) when either side is NaN. Strings
+    /// are compared by UTF-16 code unit.
+    pub fn less_than(&self, other: &Type) -> Option<bool> {
+        match (self.to_primitive(), other.to_primitive()) {
+            (Type::TextString(l), Type::TextString(r)) => {
+                Some(l.encode_utf16().lt(r.encode_utf16()))
+            }
+            (l, r) => {
+                let (l, r) = (f64::from(l.to_number()), f64::from(r.to_number()));
+                match l.is_nan() || r.is_nan() {
+                    true => None,
+                    false => Some(l < r),
                 }
-                _ => panic!("Not implemented!"),
-            },
-            Boolean(boolean) => Boolean(boolean),
-            TextString(lhs) => match rhs {
-                TextString(r) => Number(Num::NaN),
-                Number(r) => {
-                    let mut l = lhs;
-                    l.push_str(&r.to_string());
-                    TextString(l)
+            }
+        }
+    }
+
+    /// Formats the value the way Node's REPL and `console.log` display it.
+    pub fn inspect(&self) -> String {
+        self.inspect_depth(0)
+    }
+
+    fn inspect_depth(&self, depth: usize) -> String {
+        use Type::*;
+        match self {
+            TextString(string) => quote(string),
+            Function(function) if function.name.is_empty() => {
+                String::from("[Function (anonymous)]")
+            }
+            Function(function) => format!("[Function: {}]", function.name),
+            // Node writes negative zero as `-0` even though it converts to the string `0`.
+            Number(number)
+                if f64::from(*number) == 0f64 && f64::from(*number).is_sign_negative() =>
+            {
+                String::from("-0")
+            }
+            Object(_) if depth > 2 => String::from("[Object]"),
+            Array(_) if depth > 2 => String::from("[Array]"),
+            Array(array) => {
+                let array = array.borrow();
+                if array.is_empty() {
+                    return String::from("[]");
                 }
-                _ => panic!("Not implemented!"),
-            },
-            Object(object) => Object(object),
-            Function(name) => Function(String::from(name)),
+                let elements: Vec<String> = array
+                    .iter()
+                    .map(|element| element.inspect_depth(depth + 1))
+                    .collect();
+                format!("[ {} ]", elements.join(", "))
+            }
+            Object(object) => {
+                let object = object.borrow();
+                if object.is_empty() {
+                    return String::from("{}");
+                }
+                let properties: Vec<String> = object
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = value.inspect_depth(depth + 1);
+                        if is_identifier(key) {
+                            format!("{}: {}", key, value)
+                        } else {
+                            format!("{}: {}", quote(key), value)
+                        }
+                    })
+                    .collect();
+                format!("{{ {} }}", properties.join(", "))
+            }
+            value => value.to_string(),
         }
     }
 }
 
-impl<'a> Mul for Type<'a> {
-    type Output = Type<'a>;
+/// The `+` operator, which concatenates if either side is a string once objects have been
+/// converted to primitives, and adds numbers otherwise.
+impl Add for Type {
+    type Output = Type;
 
-    fn mul(self, rhs: Type) -> Self::Output {
-        use Type::*;
-        match (self, rhs) {
-            (Number(Num::F64(l)), Number(Num::F64(r))) => Number(Num::F64(l * r)),
-            _ => Number(Num::NaN),
+    fn add(self, rhs: Type) -> Self::Output {
+        match (self.to_primitive(), rhs.to_primitive()) {
+            (l @ Type::TextString(_), r) | (l, r @ Type::TextString(_)) => {
+                Type::TextString(format!("{}{}", l, r))
+            }
+            (l, r) => Type::Number(l.to_number() + r.to_number()),
         }
     }
 }
 
-impl<'a> Div for Type<'a> {
-    type Output = Type<'a>;
+impl Sub for Type {
+    type Output = Type;
+
+    fn sub(self, rhs: Type) -> Self::Output {
+        Type::Number(self.to_number() - rhs.to_number())
+    }
+}
+
+impl Mul for Type {
+    type Output = Type;
+
+    fn mul(self, rhs: Type) -> Self::Output {
+        Type::Number(self.to_number() * rhs.to_number())
+    }
+}
+
+impl Div for Type {
+    type Output = Type;
 
     fn div(self, rhs: Type) -> Self::Output {
-        use Type::*;
-        match (self, rhs) {
-            (Number(Num::F64(l)), Number(Num::F64(r))) => Number(Num::F64(l / r)),
-            _ => Number(Num::NaN),
-        }
+        Type::Number(self.to_number() / rhs.to_number())
     }
 }
 
-impl<'a> Clone for Type<'a> {
-    fn clone(&self) -> Self {
-        use Type::*;
+/// A function implemented in Rust rather than JavaScript, called with its arguments and `this`.
+pub(crate) type NativeFunction = Box<dyn Fn(&[Type], &Type) -> Result<Type, JsError>>;
+
+pub(crate) enum FunctionBody {
+    /// Statements parsed once when the function is declared.
+    Script {
+        params: Rc<[String]>,
+        body: Rc<[Statement]>,
+        /// The scope the function was created in, shared with everything else that
+        /// captured it.
+        environment: Environment,
+        /// Arrow functions take `this` and `arguments` from `environment` instead of
+        /// binding their own.
+        arrow: bool,
+        source: Rc<str>,
+    },
+    Native(NativeFunction),
+}
+
+impl fmt::Debug for FunctionBody {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Null => Null,
-            Undefined => Undefined,
-            Number(number) => Number(*number),
-            Boolean(boolean) => Boolean(*boolean),
-            TextString(string) => TextString(String::from(string)),
-            Object(object) => Object(*object),
-            Function(name) => Function(String::from(name)),
+            FunctionBody::Script {
+                params,
+                body,
+                arrow,
+                ..
+            } => f
+                .debug_struct("Script")
+                .field("params", params)
+                .field("body", body)
+                .field("arrow", arrow)
+                .finish_non_exhaustive(),
+            FunctionBody::Native(_) => write!(f, "Native"),
         }
     }
 }
 
+/// A function value. Its body is private to the interpreter, so embedders can only create native
+/// functions and read a function's name.
 #[derive(Debug)]
-pub struct Function<'a> {
-    pub arguments: HashMap<String, Type<'a>>,
-    pub body: String,
+pub struct Function {
+    pub(crate) name: String,
+    pub(crate) body: FunctionBody,
+}
+
+impl Function {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn native<F>(name: &str, call: F) -> Type
+    where
+        F: Fn(&[Type], &Type) -> Result<Type, JsError> + 'static,
+    {
+        Type::Function(Rc::new(Function {
+            name: String::from(name),
+            body: FunctionBody::Native(Box::new(call)),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod properties_tests {
+    use super::*;
+
+    fn keys(properties: &Properties) -> Vec<&str> {
+        properties.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn keys_keep_their_insertion_order() {
+        let mut properties = Properties::new();
+        for key in ["b", "a", "2", "c", "10", "01"] {
+            properties.insert(String::from(key), Type::Null);
+        }
+        assert_eq!(keys(&properties), ["2", "10", "b", "a", "c", "01"]);
+        properties.insert(String::from("b"), Type::Boolean(true));
+        assert_eq!(keys(&properties), ["2", "10", "b", "a", "c", "01"]);
+        assert!(matches!(properties.get("b"), Some(Type::Boolean(true))));
+    }
+
+    #[test]
+    fn removing_a_key_keeps_the_others_in_order() {
+        let mut properties = Properties::new();
+        for key in ["a", "b", "c"] {
+            properties.insert(String::from(key), Type::TextString(String::from(key)));
+        }
+        assert!(matches!(properties.remove("a"), Some(Type::TextString(a)) if a == "a"));
+        assert!(properties.remove("a").is_none());
+        assert_eq!(keys(&properties), ["b", "c"]);
+        assert!(matches!(&properties["c"], Type::TextString(c) if c == "c"));
+        properties.insert(String::from("a"), Type::Null);
+        assert_eq!(keys(&properties), ["b", "c", "a"]);
+        assert_eq!(properties.len(), 3);
+    }
+}
+
+#[cfg(test)]
+mod inspect_tests {
+    use super::*;
+
+    #[test]
+    fn inspect_primitives() {
+        assert_eq!(Type::Undefined.inspect(), "undefined");
+        assert_eq!(Type::Null.inspect(), "null");
+        assert_eq!(Type::Number(Num::F64(20.2)).inspect(), "20.2");
+        assert_eq!(Type::Boolean(false).inspect(), "false");
+    }
+
+    #[test]
+    fn inspect_strings() {
+        assert_eq!(Type::TextString(String::from("jake")).inspect(), "'jake'");
+        assert_eq!(Type::TextString(String::from("it's")).inspect(), "\"it's\"");
+        assert_eq!(Type::TextString(String::from("a\nb")).inspect(), "'a\\nb'");
+    }
+
+    #[test]
+    fn inspect_objects() {
+        let mut inner = Properties::new();
+        inner.insert(
+            String::from("propA"),
+            Type::TextString(String::from("test")),
+        );
+        let mut outer = Properties::new();
+        outer.insert(String::from("propB"), Type::Number(Num::F64(20f64)));
+        outer.insert(
+            String::from("prop-c"),
+            Type::Object(Rc::new(RefCell::new(inner))),
+        );
+        let object = Type::Object(Rc::new(RefCell::new(outer)));
+        assert_eq!(
+            object.inspect(),
+            "{ propB: 20, 'prop-c': { propA: 'test' } }"
+        );
+        assert_eq!(
+            Type::Object(Rc::new(RefCell::new(Properties::new()))).inspect(),
+            "{}"
+        );
+    }
+
+    #[test]
+    fn inspect_arrays() {
+        let array = |elements| Type::Array(Rc::new(RefCell::new(elements)));
+        assert_eq!(array(vec![]).inspect(), "[]");
+        let strings = array(vec![Type::TextString(String::from("a")), Type::Null]);
+        assert_eq!(strings.inspect(), "[ 'a', null ]");
+        let deep = array(vec![array(vec![array(vec![array(vec![])])])]);
+        assert_eq!(array(vec![deep]).inspect(), "[ [ [ [Array] ] ] ]");
+        assert_eq!(Type::Number(Num::F64(-0f64)).inspect(), "-0");
+    }
+
+    #[test]
+    fn inspect_functions() {
+        let log = Function::native("log", |_, _| Ok(Type::Undefined));
+        assert_eq!(log.inspect(), "[Function: log]");
+        assert_eq!(log.to_string(), "function log() { [native code] }");
+        let anonymous = Function::native("", |_, _| Ok(Type::Undefined));
+        assert_eq!(anonymous.inspect(), "[Function (anonymous)]");
+    }
+}
+
+#[cfg(test)]
+mod test_support {
+    use super::*;
+
+    pub fn number(number: f64) -> Type {
+        Type::Number(Num::F64(number))
+    }
+
+    pub fn string(string: &str) -> Type {
+        Type::TextString(String::from(string))
+    }
+
+    pub fn array(elements: Vec<Type>) -> Type {
+        Type::Array(Rc::new(RefCell::new(elements)))
+    }
+
+    pub fn object() -> Type {
+        Type::Object(Rc::new(RefCell::new(Properties::new())))
+    }
+}
+
+#[cfg(test)]
+mod coercion_tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn numbers_to_strings() {
+        for (number, expected) in [
+            (0f64, "0"),
+            (-0f64, "0"),
+            (1.5, "1.5"),
+            (-42f64, "-42"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (1e21, "1e+21"),
+            (123e20, "1.23e+22"),
+            (1e20, "100000000000000000000"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-1.5e-10, "-1.5e-10"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+            (f64::NAN, "NaN"),
+        ] {
+            assert_eq!(Num::F64(number).to_string(), expected, "{:?}", number);
+        }
+        assert_eq!(Num::NaN.to_string(), "NaN");
+    }
+
+    #[test]
+    fn arrays_to_primitives() {
+        assert_eq!(array(vec![]).to_primitive().inspect(), "''");
+        let elements = vec![number(1f64), Type::Null, Type::Undefined, string("a")];
+        assert_eq!(array(elements).to_string(), "1,,,a");
+        let nested = array(vec![number(1f64), array(vec![number(2f64), number(3f64)])]);
+        assert_eq!(nested.to_string(), "1,2,3");
+        assert_eq!(array(vec![number(5f64)]).to_number(), Num::F64(5f64));
+        assert_eq!(array(vec![]).to_number(), Num::F64(0f64));
+        assert_eq!(
+            array(vec![number(1f64), number(2f64)]).to_number(),
+            Num::NaN
+        );
+    }
+
+    #[test]
+    fn addition() {
+        for (l, r, expected) in [
+            (Type::Boolean(true), Type::Boolean(true), "2"),
+            (Type::Null, number(10f64), "10"),
+            (number(10f64), Type::Undefined, "NaN"),
+            (string("jake"), number(10f64), "'jake10'"),
+            (number(10f64), string("jake"), "'10jake'"),
+            (Type::Null, string("a"), "'nulla'"),
+            (array(vec![]), number(10f64), "'10'"),
+            (array(vec![number(1f64)]), array(vec![number(2f64)]), "'12'"),
+            (Type::Boolean(false), Type::Null, "0"),
+        ] {
+            assert_eq!(
+                (l.clone() + r.clone()).inspect(),
+                expected,
+                "{:?} + {:?}",
+                l,
+                r
+            );
+        }
+    }
+
+    #[test]
+    fn other_arithmetic() {
+        assert_eq!((string("jake") - string("e")).to_number(), Num::NaN);
+        for (result, expected) in [
+            (string("10") - number(4f64), 6f64),
+            (Type::Boolean(true) - Type::Null, 1f64),
+            (string("3") * string("4"), 12f64),
+            (array(vec![number(6f64)]) / string(" 2 "), 3f64),
+        ] {
+            assert_eq!(result.to_number(), Num::F64(expected));
+        }
+        assert_eq!((Type::Undefined * number(2f64)).to_number(), Num::NaN);
+    }
+}
+
+#[cfg(test)]
+mod comparison_tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn strings_to_numbers() {
+        for (string, expected) in [
+            ("", 0f64),
+            ("  42\n", 42f64),
+            ("-1.5e3", -1500f64),
+            (".5", 0.5),
+            ("0x1F", 31f64),
+            ("0b101", 5f64),
+            ("0o17", 15f64),
+            ("-Infinity", f64::NEG_INFINITY),
+        ] {
+            assert_eq!(string_to_number(string), Num::F64(expected), "{:?}", string);
+        }
+        for string in ["abc", "1px", "inf", "NaN", "-0x10", "0x", "1e"] {
+            assert_eq!(string_to_number(string), Num::NaN, "{:?}", string);
+        }
+    }
+
+    #[test]
+    fn numbers_to_int32() {
+        for (number, expected) in [
+            (Num::F64(1.9), 1),
+            (Num::F64(-1.9), -1),
+            (Num::F64(2147483648f64), -2147483648),
+            (Num::F64(4294967297f64), 1),
+            (Num::F64(f64::INFINITY), 0),
+            (Num::NaN, 0),
+        ] {
+            assert_eq!(number.to_int32(), expected, "{}", number);
+        }
+    }
+
+    #[test]
+    fn numbers_to_uint32() {
+        assert_eq!(Num::F64(-1f64).to_uint32(), 4294967295);
+        assert_eq!(Num::F64(4294967296.5).to_uint32(), 0);
+        assert_eq!(Num::F64(-0.5).to_uint32(), 0);
+        assert_eq!(Num::NaN.to_uint32(), 0);
+    }
+
+    #[test]
+    fn remainder() {
+        assert_eq!(Num::F64(7f64) % Num::F64(3f64), Num::F64(1f64));
+        assert_eq!(Num::F64(-7f64) % Num::F64(3f64), Num::F64(-1f64));
+        assert_eq!(Num::F64(5.5) % Num::F64(2f64), Num::F64(1.5));
+        assert_eq!(Num::F64(1f64) % Num::F64(0f64), Num::NaN);
+        assert_eq!(Num::F64(f64::INFINITY) % Num::F64(2f64), Num::NaN);
+        assert_eq!(Num::F64(2f64) % Num::F64(f64::INFINITY), Num::F64(2f64));
+    }
+
+    #[test]
+    fn strict_equality() {
+        assert!(number(1f64).strict_equals(&number(1f64)));
+        assert!(!number(1f64).strict_equals(&string("1")));
+        assert!(!Type::Number(Num::NaN).strict_equals(&Type::Number(Num::NaN)));
+        assert!(!Type::Null.strict_equals(&Type::Undefined));
+        let a = object();
+        assert!(a.strict_equals(&a.clone()));
+        assert!(!a.strict_equals(&object()));
+    }
+
+    #[test]
+    fn loose_equality() {
+        assert!(Type::Null.loose_equals(&Type::Undefined));
+        assert!(!Type::Null.loose_equals(&number(0f64)));
+        assert!(!Type::Undefined.loose_equals(&Type::Boolean(false)));
+        assert!(number(1f64).loose_equals(&string("1")));
+        assert!(string("").loose_equals(&number(0f64)));
+        assert!(Type::Boolean(true).loose_equals(&string("1")));
+        assert!(Type::Boolean(false).loose_equals(&string("0")));
+        assert!(object().loose_equals(&string("[object Object]")));
+        assert!(!Type::Number(Num::NaN).loose_equals(&Type::Number(Num::NaN)));
+        assert!(!object().loose_equals(&object()));
+    }
+
+    #[test]
+    fn relational_comparison() {
+        assert_eq!(number(1f64).less_than(&number(2f64)), Some(true));
+        assert_eq!(string("10").less_than(&string("9")), Some(true));
+        assert_eq!(string("10").less_than(&number(9f64)), Some(false));
+        assert_eq!(Type::Null.less_than(&number(1f64)), Some(true));
+        assert_eq!(Type::Undefined.less_than(&number(1f64)), None);
+        assert_eq!(string("a").less_than(&number(1f64)), None);
+        // U+FF61 sorts after U+1F600 by code point but before it by UTF-16 code unit.
+        assert_eq!(
+            string("\u{1F600}").less_than(&string("\u{FF61}")),
+            Some(true)
+        );
+    }
 }
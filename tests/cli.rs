@@ -0,0 +1,54 @@
+//! Runs the `rustjs` binary the way a shell would.
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+#[test]
+fn closed_stdout_does_not_panic() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rustjs"))
+        .args(["-e", "for (var i = 0; i < 100000; i++) console.log(i)"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the rustjs binary should run");
+    // Read one chunk and close the pipe, as `rustjs script.js | head -1` would.
+    let mut stdout = child.stdout.take().unwrap();
+    stdout.read_exact(&mut [0; 2]).unwrap();
+    drop(stdout);
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut stderr)
+        .unwrap();
+    let status = child.wait().unwrap();
+    assert!(status.success(), "exited with {}: {}", status, stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn deep_nesting_is_an_uncaught_error() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rustjs"))
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the rustjs binary should run");
+    let source = format!("{}1{}", "(".repeat(200_000), ")".repeat(200_000));
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.starts_with("Uncaught RangeError: Maximum call stack size exceeded"),
+        "{}",
+        stderr
+    );
+}
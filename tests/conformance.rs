@@ -0,0 +1,111 @@
+//! Runs every script in `tests/fixtures` and `javascript` through the `rustjs` binary and checks
+//! that what it prints matches the `.expected` file next to it, which holds Node's output for the
+//! same script.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The directories holding scripts to check. `javascript` is the scratch script the interpreter
+/// grew up running.
+const DIRECTORIES: [&str; 2] = ["tests/fixtures", "javascript"];
+
+fn fixtures() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut scripts = Vec::new();
+    for directory in DIRECTORIES {
+        let mut found: Vec<PathBuf> = fs::read_dir(root.join(directory))
+            .unwrap_or_else(|_| panic!("{} should exist", directory))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "js"))
+            .collect();
+        found.sort();
+        scripts.extend(found);
+    }
+    scripts
+}
+
+/// Describes how `actual` differs from `expected`, or `None` if they match.
+fn difference(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(expected), Some(actual)) if expected == actual => continue,
+            (None, None) => break,
+            (expected, actual) => {
+                return Some(format!(
+                    "line {}: expected {:?}, got {:?}",
+                    line,
+                    expected.unwrap_or("<end of output>"),
+                    actual.unwrap_or("<end of output>")
+                ))
+            }
+        }
+    }
+    Some(String::from("output differs in trailing whitespace"))
+}
+
+#[test]
+fn fixtures_match_expected_output() {
+    let scripts = fixtures();
+    assert!(!scripts.is_empty(), "no fixtures found");
+    let mut failures = Vec::new();
+    for script in &scripts {
+        let name = script
+            .strip_prefix(env!("CARGO_MANIFEST_DIR"))
+            .unwrap()
+            .display();
+        let expected = match fs::read_to_string(script.with_extension("expected")) {
+            Ok(expected) => expected,
+            Err(_) => {
+                failures.push(format!("{}: missing .expected file", name));
+                continue;
+            }
+        };
+        let output = Command::new(env!("CARGO_BIN_EXE_rustjs"))
+            .arg(script)
+            .output()
+            .expect("the rustjs binary should run");
+        let actual = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            failures.push(format!(
+                "{}: exited with {}\n{}",
+                name,
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        } else if let Some(difference) = difference(&expected, &actual) {
+            failures.push(format!("{}: {}", name, difference));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} fixtures failed:\n{}",
+        failures.len(),
+        scripts.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn differences_point_at_the_first_changed_line() {
+    assert_eq!(difference("a\nb\n", "a\nb\n"), None);
+    assert_eq!(
+        difference("a\nb\n", "a\nc\n"),
+        Some(String::from("line 2: expected \"b\", got \"c\""))
+    );
+    assert_eq!(
+        difference("a\n", "a\nb\n"),
+        Some(String::from(
+            "line 2: expected \"<end of output>\", got \"b\""
+        ))
+    );
+    assert_eq!(
+        difference("a\n", "a"),
+        Some(String::from("output differs in trailing whitespace"))
+    );
+}
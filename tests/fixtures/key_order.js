@@ -0,0 +1,15 @@
+// Objects list their keys in the order they were added, except that keys which are array
+// indexes come first, in ascending order.
+var order = { zebra: 1, apple: 2, mango: 3 };
+console.log(order);
+
+order.banana = 4;
+order.apple = 5;
+console.log(order);
+
+delete order.zebra;
+order.zebra = 6;
+console.log(order);
+
+console.log({ b: 1, 10: 2, a: 3, 2: 4, '01': 5 });
+console.log({ ...{ y: 1, x: 2 }, w: 3 });
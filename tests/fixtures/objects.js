@@ -0,0 +1,33 @@
+var name = 'jake';
+var key = 'colour';
+var point = { x: 1, y: 2 };
+
+var person = {
+  name,
+  [key]: 'blue',
+  'favourite number': 7,
+  42: 'answer',
+  greet(greeting) {
+    return greeting + ', ' + this.name;
+  },
+  shout: function () {
+    return this.greet('HEY');
+  },
+  ...point,
+  y: 3,
+};
+
+console.log(person.name, person.colour, person['favourite number'], person[42]);
+console.log(person.greet('hello'), person['shout']());
+console.log(person.greet.name, person.shout.name);
+console.log(person.x, person.y, person.missing, typeof person.missing);
+
+person.address = {};
+person.address.city = 'Leeds';
+person['address'].postcode = 'LS1';
+person.x += 10;
+console.log(person.address.city, person.address.postcode, person.x);
+
+var copy = { ...person, name: 'sam' };
+console.log(copy.name, person.name, copy.address === person.address);
+console.log({ ...'hi' }[1], { ...[10, 20] }[0], 'name' in copy);